@@ -7,11 +7,14 @@
 //! * `/v1/latest_block` - returns latest processed block
 //! * `/v1/confidence/{block_number}` - returns calculated confidence for a given block number
 //! * `/v1/appdata/{block_number}` - returns decoded extrinsic data for configured app_id and given block number
+//! * `/metrics` - returns metrics in Prometheus text format (if `prometheus` feature is enabled)
 
 use crate::api::v2;
 use crate::data::Database;
 use crate::network::p2p;
 use crate::shutdown::Controller;
+#[cfg(feature = "prometheus")]
+use crate::telemetry::prometheus;
 use crate::types::IdentityConfig;
 use crate::{
 	api::v1,
@@ -25,6 +28,8 @@ use std::{
 	str::FromStr,
 	sync::{Arc, Mutex},
 };
+#[cfg(feature = "prometheus")]
+use tracing::error;
 use tracing::info;
 use warp::{Filter, Reply};
 
@@ -39,6 +44,8 @@ pub struct Server<T: Database> {
 	pub ws_clients: v2::types::WsClients,
 	pub shutdown: Controller<String>,
 	pub p2p_client: p2p::Client,
+	#[cfg(feature = "prometheus")]
+	pub prometheus_metrics: Arc<prometheus::Metrics>,
 }
 
 fn health_route() -> impl Filter<Extract = impl Reply, Error = warp::Rejection> + Clone {
@@ -48,6 +55,23 @@ fn health_route() -> impl Filter<Extract = impl Reply, Error = warp::Rejection>
 		.map(|_| warp::reply::with_status("", warp::http::StatusCode::OK))
 }
 
+#[cfg(feature = "prometheus")]
+fn metrics_route(
+	metrics: Arc<prometheus::Metrics>,
+) -> impl Filter<Extract = impl Reply, Error = warp::Rejection> + Clone {
+	warp::get()
+		.and(warp::path("metrics"))
+		.and(warp::path::end())
+		.map(move || match metrics.encode() {
+			Ok(body) => warp::reply::with_header(body, "content-type", "text/plain; version=0.0.4")
+				.into_response(),
+			Err(error) => {
+				error!("Unable to encode metrics: {error:#}");
+				warp::http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+			},
+		})
+}
+
 impl<T: Database + Clone + Send + Sync + 'static> Server<T> {
 	/// Creates a HTTP server that needs to be spawned into a runtime
 	pub fn bind(self) -> impl Future<Output = ()> {
@@ -58,6 +82,9 @@ impl<T: Database + Clone + Send + Sync + 'static> Server<T> {
 			..
 		} = self.cfg.clone();
 
+		#[cfg(feature = "prometheus")]
+		let prometheus_metrics = self.prometheus_metrics.clone();
+
 		let v1_api = v1::routes(
 			self.db.clone(),
 			app_id,
@@ -81,7 +108,10 @@ impl<T: Database + Clone + Send + Sync + 'static> Server<T> {
 			.allow_header("content-type")
 			.allow_methods(vec!["GET", "POST", "DELETE"]);
 
-		let routes = health_route().or(v1_api).or(v2_api).with(cors);
+		let routes = health_route().or(v1_api).or(v2_api);
+		#[cfg(feature = "prometheus")]
+		let routes = routes.or(metrics_route(prometheus_metrics));
+		let routes = routes.with(cors);
 
 		let addr = SocketAddr::from_str(format!("{host}:{port}").as_str())
 			.wrap_err("Unable to parse host address from config")
@@ -94,3 +124,28 @@ impl<T: Database + Clone + Send + Sync + 'static> Server<T> {
 		server
 	}
 }
+
+#[cfg(all(test, feature = "prometheus"))]
+mod tests {
+	use super::metrics_route;
+	use crate::{
+		telemetry::{prometheus, MetricValue, Metrics as _},
+		types::Origin,
+	};
+	use std::sync::Arc;
+
+	#[tokio::test]
+	async fn test_metrics_route() {
+		let metrics = Arc::new(prometheus::Metrics::new(Origin::Internal).unwrap());
+		metrics.record(MetricValue::BlockHeight(42)).await.unwrap();
+
+		let response = warp::test::request()
+			.method("GET")
+			.path("/metrics")
+			.reply(&metrics_route(metrics))
+			.await;
+		assert_eq!(response.status(), 200);
+		let body = String::from_utf8(response.body().to_vec()).unwrap();
+		assert!(body.contains("avail_light_block_height 42"));
+	}
+}
@@ -2,7 +2,6 @@ use super::types::{AppDataQuery, ClientResponse, ConfidenceResponse, LatestBlock
 use crate::{
 	api::v1::types::{Extrinsics, ExtrinsicsDataResponse},
 	data::{Database, Key},
-	network::rpc::cell_count_for_confidence,
 	types::{Mode, OptionBlockRange, RuntimeConfig, State},
 	utils::calculate_confidence,
 };
@@ -46,7 +45,9 @@ pub fn confidence(
 
 	let count = match db.get(Key::VerifiedCellCount(block_num)) {
 		Ok(Some(count)) => count,
-		Ok(None) if is_synced(block_num, state) => cell_count_for_confidence(cfg.confidence),
+		Ok(None) if is_synced(block_num, state) => {
+			cfg.confidence_calculator.cells_needed(*cfg.confidence)
+		},
 		Ok(None) => return ClientResponse::NotFinalized,
 		Err(error) => return ClientResponse::Error(error),
 	};
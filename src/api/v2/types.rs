@@ -3,7 +3,6 @@ use avail_subxt::api::runtime_types::{
 	bounded_collections::bounded_vec::BoundedVec,
 };
 use base64::{engine::general_purpose, DecodeError, Engine};
-use codec::Encode;
 use color_eyre::{
 	eyre::{eyre, WrapErr},
 	Report, Result,
@@ -12,9 +11,11 @@ use derive_more::From;
 use hyper::{http, StatusCode};
 use kate_recovery::{com::AppData, commitments, config, matrix::Partition};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
-use sp_core::{blake2_256, H256};
+use sp_core::H256;
 use std::{
+	cmp::Ordering,
 	collections::{HashMap, HashSet},
+	fmt::{self, Display, Formatter},
 	sync::Arc,
 };
 use tokio::sync::{mpsc::UnboundedSender, RwLock};
@@ -29,7 +30,7 @@ use crate::{
 	types::{
 		self, block_matrix_partition_format, BlockVerified, OptionBlockRange, RuntimeConfig, State,
 	},
-	utils::{decode_app_data, OptionalExtension},
+	utils::{decode_app_data, HeaderExt, OptionalExtension},
 };
 
 #[derive(Debug)]
@@ -359,6 +360,38 @@ impl Reply for Header {
 	}
 }
 
+impl Display for Header {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"Header {{ number={}, hash={:?}, rows={}, cols={} }}",
+			self.number, self.hash, self.extension.rows, self.extension.cols
+		)
+	}
+}
+
+/// Headers are compared by block number only, so that they can be ordered for processing.
+/// Equality is intentionally defined by block number as well, not by hash or content.
+impl PartialEq for Header {
+	fn eq(&self, other: &Self) -> bool {
+		self.number == other.number
+	}
+}
+
+impl Eq for Header {}
+
+impl PartialOrd for Header {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Header {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.number.cmp(&other.number)
+	}
+}
+
 #[derive(Debug, Clone)]
 struct Commitment([u8; config::COMMITMENT_SIZE]);
 
@@ -412,7 +445,7 @@ impl TryFrom<avail_subxt::primitives::Header> for Header {
 
 	fn try_from(header: avail_subxt::primitives::Header) -> Result<Self> {
 		Ok(Header {
-			hash: Encode::using_encoded(&header, blake2_256).into(),
+			hash: header.hash(),
 			parent_hash: header.parent_hash,
 			number: header.number,
 			state_root: header.state_root,
@@ -810,7 +843,7 @@ pub enum WsError {
 
 #[cfg(test)]
 mod tests {
-	use std::time::Duration;
+	use std::{cmp::Reverse, collections::BinaryHeap, time::Duration};
 
 	use avail_subxt::api::runtime_types::avail_core::data_lookup::compact::CompactDataLookup;
 	use sp_core::H256;
@@ -834,29 +867,70 @@ mod tests {
 		}
 	}
 
+	fn header(number: u32) -> Header {
+		Header {
+			hash: H256::default(),
+			parent_hash: H256::default(),
+			number,
+			state_root: H256::default(),
+			extrinsics_root: H256::default(),
+			extension: super::Extension {
+				rows: 1,
+				cols: 1,
+				data_root: H256::default(),
+				commitments: vec![],
+				app_lookup: CompactDataLookup {
+					size: 0,
+					index: vec![],
+				},
+			},
+		}
+	}
+
 	fn header_verified() -> PublishMessage {
 		PublishMessage::HeaderVerified(Box::new(HeaderMessage {
 			block_number: 1,
-			header: Header {
-				hash: H256::default(),
-				parent_hash: H256::default(),
-				number: 1,
-				state_root: H256::default(),
-				extrinsics_root: H256::default(),
-				extension: super::Extension {
-					rows: 1,
-					cols: 1,
-					data_root: H256::default(),
-					commitments: vec![],
-					app_lookup: CompactDataLookup {
-						size: 0,
-						index: vec![],
-					},
-				},
-			},
+			header: header(1),
 		}))
 	}
 
+	#[test]
+	fn header_display() {
+		let PublishMessage::HeaderVerified(message) = header_verified() else {
+			panic!("Invalid message type");
+		};
+		let header = message.header.to_string();
+		assert_eq!(
+			header,
+			format!(
+				"Header {{ number=1, hash={:?}, rows=1, cols=1 }}",
+				H256::default()
+			)
+		);
+		let number = header
+			.split_once("number=")
+			.and_then(|(_, rest)| rest.split_once(','))
+			.map(|(number, _)| number.parse::<u32>());
+		assert_eq!(number, Some(Ok(1)));
+	}
+
+	#[test]
+	fn header_ordering() {
+		let mut heap = BinaryHeap::new();
+		for number in [5, 1, 4, 2, 3] {
+			heap.push(Reverse(header(number)));
+		}
+		let numbers = std::iter::from_fn(|| heap.pop())
+			.map(|Reverse(header)| header.number)
+			.collect::<Vec<_>>();
+		assert_eq!(numbers, vec![1, 2, 3, 4, 5]);
+
+		let mut other = header(1);
+		other.hash = H256::repeat_byte(1);
+		assert_eq!(header(1), other);
+		assert!(header(1) < header(2));
+	}
+
 	fn confidence_achieved() -> PublishMessage {
 		PublishMessage::ConfidenceAchieved(ConfidenceMessage {
 			block_number: 1,
@@ -203,10 +203,28 @@ impl Client for AppClient {
 		for (i, row) in rows {
 			result[i as usize] = Some(row);
 		}
+		validate_row_lengths(&result, dimensions.width())?;
 		Ok(result)
 	}
 }
 
+/// Checks that each fetched row contains exactly `expected_cols` cells of data
+fn validate_row_lengths(rows: &[Option<Vec<u8>>], expected_cols: usize) -> Result<()> {
+	let expected_len = expected_cols * CHUNK_SIZE;
+	for (index, row) in rows.iter().enumerate() {
+		let Some(row) = row else {
+			continue;
+		};
+		if row.len() != expected_len {
+			return Err(eyre!(
+				"Invalid length of row {index}: expected {expected_len} bytes, got {}",
+				row.len()
+			));
+		}
+	}
+	Ok(())
+}
+
 fn new_data_cell(row: usize, col: usize, data: &[u8]) -> Result<DataCell> {
 	Ok(DataCell {
 		position: Position {
@@ -262,13 +280,12 @@ async fn fetch_verified(
 		.fetch_cells_from_dht(block_number, positions)
 		.await;
 
-	let (verified, mut unverified) =
-		proof::verify(block_number, dimensions, &fetched, commitments, pp)
-			.await
-			.wrap_err("Failed to verify fetched cells")?;
+	let summary = proof::verify(block_number, dimensions, &fetched, commitments, pp)
+		.await
+		.wrap_err("Failed to verify fetched cells")?;
 
-	fetched.retain(|cell| verified.contains(&cell.position));
-	unfetched.append(&mut unverified);
+	fetched.retain(|cell| !summary.failed.contains(&cell.position));
+	unfetched.extend(summary.failed);
 
 	Ok((fetched, unfetched))
 }
@@ -509,6 +526,15 @@ mod tests {
 	use avail_core::DataLookup;
 	use hex_literal::hex;
 	use kate_recovery::{matrix::Dimensions, testnet};
+	use test_case::test_case;
+
+	#[test_case(vec![] => true ; "no rows")]
+	#[test_case(vec![None, Some(vec![0; 4 * CHUNK_SIZE])] => true ; "valid rows")]
+	#[test_case(vec![Some(vec![0; 3 * CHUNK_SIZE])] => false ; "truncated row")]
+	#[test_case(vec![Some(vec![0; 4 * CHUNK_SIZE]), Some(vec![0; 4 * CHUNK_SIZE + 1])] => false ; "padded row")]
+	fn test_validate_row_lengths(rows: Vec<Option<Vec<u8>>>) -> bool {
+		validate_row_lengths(&rows, 4).is_ok()
+	}
 
 	#[tokio::test]
 	async fn test_process_blocks_without_rpc() {
@@ -548,6 +574,7 @@ mod tests {
 				.to_vec(),
 			}),
 			confidence: None,
+			verification: None,
 		};
 		mock_client
 			.expect_fetch_rows_from_dht()
@@ -605,6 +632,7 @@ mod tests {
 				.to_vec(),
 			}),
 			confidence: None,
+			verification: None,
 		};
 		mock_client
 			.expect_fetch_rows_from_dht()
@@ -2,12 +2,15 @@ use avail_light::{
 	data::rocks_db::RocksDB,
 	network::rpc,
 	shutdown::Controller,
-	types::{ExponentialConfig, RetryConfig, State},
+	types::{ExponentialConfig, RetryConfig, RuntimeConfig, State},
 };
 use clap::Parser;
 use color_eyre::{eyre::Context, Result};
 use kate_recovery::matrix::Position;
-use std::sync::{Arc, Mutex};
+use std::{
+	sync::{Arc, Mutex},
+	time::Duration,
+};
 
 #[derive(Parser)]
 struct CommandArgs {
@@ -33,8 +36,19 @@ async fn main() -> Result<()> {
 	});
 
 	let shutdown = Controller::new();
-	let (rpc_client, _, subscriptions) =
-		rpc::init(db, state, &[command_args.url], "DEV", retry_cfg, shutdown).await?;
+	let max_backoff = Duration::from_secs(RuntimeConfig::default().max_backoff_duration);
+	let (rpc_client, _, subscriptions) = rpc::init(
+		db,
+		state,
+		&[command_args.url],
+		"DEV",
+		retry_cfg,
+		max_backoff,
+		RuntimeConfig::default().header_cache_capacity,
+		Default::default(),
+		shutdown,
+	)
+	.await?;
 	tokio::spawn(subscriptions.run());
 
 	let mut correct: bool = true;
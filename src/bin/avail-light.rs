@@ -3,14 +3,14 @@
 use avail_core::AppId;
 use avail_light::{
 	api,
-	consts::EXPECTED_SYSTEM_VERSION,
+	consts::{ExpectedNodeVariant, EXPECTED_SYSTEM_VERSION},
 	data::rocks_db::RocksDB,
-	maintenance::StaticConfigParams,
+	maintenance::{MaintenanceEvent, StaticConfigParams},
 	network::{self, p2p, rpc},
 	shutdown::Controller,
 	sync_client::SyncClient,
 	sync_finality::SyncFinality,
-	telemetry::{self, otlp::MetricAttributes, MetricCounter, Metrics},
+	telemetry::{self, otlp::MetricAttributes, MetricCounter, MetricValue, Metrics, MetricsExt},
 	types::{CliOpts, IdentityConfig, LibP2PConfig, Network, OtelConfig, RuntimeConfig, State},
 };
 use clap::Parser;
@@ -18,16 +18,17 @@ use color_eyre::{
 	eyre::{eyre, WrapErr},
 	Result,
 };
-use kate_recovery::com::AppData;
+use kate_recovery::{com::AppData, matrix::Partition};
 use libp2p::{multiaddr::Protocol, Multiaddr};
 use std::{
 	fs,
 	net::Ipv4Addr,
 	path::Path,
 	sync::{Arc, Mutex},
+	time::Duration,
 };
 use tokio::sync::{broadcast, mpsc};
-use tracing::{error, info, metadata::ParseLevelError, trace, warn, Level, Subscriber};
+use tracing::{error, info, trace, warn, Level, Subscriber};
 use tracing_subscriber::{fmt::format, EnvFilter, FmtSubscriber};
 
 #[cfg(feature = "network-analysis")]
@@ -48,42 +49,49 @@ const CLIENT_ROLE: &str = if cfg!(feature = "crawl") {
 
 /// Light Client for Avail Blockchain
 
-fn json_subscriber(log_level: Level) -> impl Subscriber + Send + Sync {
+fn json_subscriber(log_filter: EnvFilter) -> impl Subscriber + Send + Sync {
 	FmtSubscriber::builder()
 		.json()
-		.with_env_filter(EnvFilter::new(format!("avail_light={log_level}")))
+		.with_env_filter(log_filter)
 		.with_span_events(format::FmtSpan::CLOSE)
 		.finish()
 }
 
-fn default_subscriber(log_level: Level) -> impl Subscriber + Send + Sync {
+fn default_subscriber(log_filter: EnvFilter) -> impl Subscriber + Send + Sync {
 	FmtSubscriber::builder()
-		.with_env_filter(EnvFilter::new(format!("avail_light={log_level}")))
+		.with_env_filter(log_filter)
 		.with_span_events(format::FmtSpan::CLOSE)
 		.finish()
 }
 
-fn parse_log_level(log_level: &str, default: Level) -> (Level, Option<ParseLevelError>) {
-	log_level
-		.to_uppercase()
-		.parse::<Level>()
-		.map(|log_level| (log_level, None))
-		.unwrap_or_else(|parse_err| (default, Some(parse_err)))
+/// Outcome of the light client startup
+enum Startup {
+	/// Light client is running until the shutdown is triggered
+	Running,
+	/// Command (`--validate-config` or `--check-connections`) is completed with the given reason,
+	/// and light client is not started
+	Completed(String),
 }
 
-async fn run(shutdown: Controller<String>) -> Result<()> {
+async fn run(shutdown: Controller<String>) -> Result<Startup> {
 	let opts = CliOpts::parse();
 
 	let mut cfg: RuntimeConfig = RuntimeConfig::default();
 	cfg.load_runtime_config(&opts)?;
 
-	let (log_level, parse_error) = parse_log_level(&cfg.log_level, Level::INFO);
+	let (log_filter, parse_error) = match cfg.log_filter_directives() {
+		Ok(log_filter) => (log_filter, None),
+		Err(error) => (
+			EnvFilter::new(format!("avail_light={}", Level::INFO)),
+			Some(error),
+		),
+	};
 
 	if cfg.log_format_json {
-		tracing::subscriber::set_global_default(json_subscriber(log_level))
+		tracing::subscriber::set_global_default(json_subscriber(log_filter))
 			.expect("global json subscriber is set")
 	} else {
-		tracing::subscriber::set_global_default(default_subscriber(log_level))
+		tracing::subscriber::set_global_default(default_subscriber(log_filter))
 			.expect("global default subscriber is set")
 	}
 
@@ -113,7 +121,57 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 	);
 
 	if let Some(error) = parse_error {
-		warn!("Using default log level: {}", error);
+		warn!("Using default log level: {error:#}");
+	}
+
+	if opts.check_connections {
+		let reports = rpc::health_check_nodes(
+			&cfg.full_node_ws,
+			&ExpectedNodeVariant::default(),
+			Duration::from_secs(cfg.full_node_probe_timeout),
+		)
+		.await;
+		for report in &reports {
+			info!(
+				url = %report.url,
+				reachable = report.reachable,
+				version = ?report.version,
+				version_matches = report.version_matches,
+				latency_ms = ?report.latency_ms,
+				"Full node checked"
+			);
+		}
+		let unhealthy = reports.iter().filter(|report| !report.is_healthy()).count();
+		if unhealthy > 0 {
+			return Err(eyre!(
+				"{unhealthy} of {} full nodes are unhealthy",
+				reports.len()
+			));
+		}
+		return Ok(Startup::Completed(
+			"Full node connections checked".to_string(),
+		));
+	}
+
+	if opts.validate_config {
+		let connect_timeout = Duration::from_secs(cfg.bootstrap_connect_timeout);
+		let results = cfg.validate_bootstraps(connect_timeout).await;
+		for result in &results {
+			info!(
+				peer_id = %result.peer_id,
+				addr = %result.addr,
+				reachable = result.reachable,
+				"Bootstrap peer checked"
+			);
+		}
+		let unreachable = results.iter().filter(|result| !result.reachable).count();
+		if unreachable > 0 {
+			return Err(eyre!(
+				"{unreachable} of {} bootstrap peers are unreachable",
+				results.len()
+			));
+		}
+		return Ok(Startup::Completed("Configuration validated".to_string()));
 	}
 
 	if opts.clean && Path::new(&cfg.avail_path).exists() {
@@ -131,28 +189,21 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 	let cfg_libp2p: LibP2PConfig = (&cfg).into();
 	let (id_keys, peer_id) = p2p::keypair(&cfg_libp2p)?;
 
-	let metric_attributes = MetricAttributes {
-		role: client_role.into(),
-		peer_id,
-		origin: cfg.origin.clone(),
-		avail_address: identity_cfg.avail_public_key.clone(),
-		operating_mode: cfg.operation_mode.to_string(),
-		partition_size: cfg
-			.block_matrix_partition
-			.map(|_| {
-				format!(
-					"{}/{}",
-					cfg.block_matrix_partition
-						.expect("partition doesn't exist")
-						.number,
-					cfg.block_matrix_partition
-						.expect("partition doesn't exist")
-						.fraction
-				)
-			})
-			.unwrap_or("n/a".to_string()),
-		network: Network::name(&cfg.genesis_hash),
-	};
+	let partition_size = cfg
+		.block_matrix_partition
+		.map(|Partition { number, fraction }| format!("{number}/{fraction}"))
+		.unwrap_or("n/a".to_string());
+
+	let metric_attributes = MetricAttributes::builder()
+		.role(client_role)
+		.peer_id(peer_id)
+		.origin(cfg.origin.clone())
+		.avail_address(identity_cfg.avail_public_key.clone())
+		.operating_mode(cfg.operation_mode.to_string())
+		.partition_size(partition_size)
+		.network(Network::name(&cfg.genesis_hash))
+		.build()
+		.wrap_err("Invalid metric attributes")?;
 
 	let cfg_otel: OtelConfig = (&cfg).into();
 	let ot_metrics = Arc::new(
@@ -165,6 +216,30 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 		.wrap_err("Unable to initialize OpenTelemetry service")?,
 	);
 
+	#[cfg(feature = "prometheus")]
+	let prometheus_metrics = Arc::new(
+		telemetry::prometheus::Metrics::new(cfg.origin.clone())
+			.wrap_err("Unable to initialize Prometheus metrics")?,
+	);
+	// Both backends receive every metric update
+	#[cfg(feature = "prometheus")]
+	let metrics = Arc::new(telemetry::CombinedMetrics::new(
+		ot_metrics.clone(),
+		prometheus_metrics.clone(),
+	));
+	#[cfg(not(feature = "prometheus"))]
+	let metrics = ot_metrics.clone();
+
+	// Export buffered metrics and wait for pending exports before the shutdown completes
+	let shutdown_metrics = metrics.clone();
+	let shutdown_triggered = shutdown.triggered_shutdown();
+	tokio::spawn(shutdown.with_delay(async move {
+		shutdown_triggered.await;
+		if let Err(error) = shutdown_metrics.flush_all().await {
+			error!("Unable to export metrics on shutdown: {error:#}");
+		}
+	})?);
+
 	// Create sender channel for P2P event loop commands
 	let (p2p_event_loop_sender, p2p_event_loop_receiver) = mpsc::unbounded_channel();
 
@@ -182,7 +257,7 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 		shutdown.with_cancel(
 			p2p_event_loop
 				.await
-				.run(ot_metrics.clone(), p2p_event_loop_receiver),
+				.run(metrics.clone(), p2p_event_loop_receiver),
 		),
 	);
 
@@ -225,6 +300,18 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 	let public_params_len = hex::encode(raw_pp).len();
 	trace!("Public params ({public_params_len}): hash: {public_params_hash}");
 
+	// Full nodes are probed in the background, so the startup is not delayed by unreachable nodes
+	let probe_cfg = cfg.clone();
+	tokio::spawn(shutdown.with_cancel(async move {
+		let probe_timeout = Duration::from_secs(probe_cfg.full_node_probe_timeout);
+		let healthy_nodes = probe_cfg.full_node_ws_healthy(probe_timeout).await;
+		if healthy_nodes.is_empty() {
+			warn!("None of the configured full nodes is reachable");
+		} else {
+			info!(nodes = ?healthy_nodes, "Reachable full nodes");
+		}
+	}));
+
 	let state = Arc::new(Mutex::new(State::default()));
 	let (rpc_client, rpc_events, rpc_subscriptions) = rpc::init(
 		db.clone(),
@@ -232,10 +319,25 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 		&cfg.full_node_ws,
 		&cfg.genesis_hash,
 		cfg.retry_config.clone(),
+		Duration::from_secs(cfg.max_backoff_duration),
+		cfg.header_cache_capacity,
+		(&cfg).into(),
 		shutdown.clone(),
 	)
 	.await?;
 
+	let connection_result = rpc_client.connection_result();
+	metrics
+		.record_or_warn(MetricValue::NodeConnectionAttempts(
+			connection_result.nodes_tried,
+		))
+		.await;
+	metrics
+		.record_or_warn(MetricValue::NodeConnectionRetries(
+			connection_result.retries,
+		))
+		.await;
+
 	// Subscribing to RPC events before first event is published
 	let publish_rpc_event_receiver = rpc_events.subscribe();
 	let first_header_rpc_event_receiver = rpc_events.subscribe();
@@ -264,12 +366,12 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 		))
 		.await
 	{
-		Ok(Err(report)) => {
+		Ok(Err(error)) => {
 			if !rpc_subscriptions_handle.is_finished() {
-				return Err(report);
+				return Err(error.into());
 			}
 			let Ok(Ok(Err(subscriptions_error))) = rpc_subscriptions_handle.await else {
-				return Err(report);
+				return Err(error.into());
 			};
 			return Err(eyre!(subscriptions_error));
 		},
@@ -302,6 +404,8 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 		ws_clients: ws_clients.clone(),
 		shutdown: shutdown.clone(),
 		p2p_client: p2p_client.clone(),
+		#[cfg(feature = "prometheus")]
+		prometheus_metrics,
 	};
 	tokio::task::spawn(shutdown.with_cancel(server.bind()));
 
@@ -352,7 +456,7 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 			crawler_rpc_event_receiver,
 			p2p_client.clone(),
 			cfg.crawl.crawl_block_delay,
-			ot_metrics.clone(),
+			metrics.clone(),
 			cfg.crawl.crawl_block_mode,
 			partition.unwrap_or(avail_light::crawl_client::ENTIRE_BLOCK),
 		)));
@@ -365,6 +469,9 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 		rpc_client.clone(),
 		pp.clone(),
 		cfg.disable_rpc,
+		cfg.cell_fetch_strategy,
+		cfg.max_cells_per_rpc(),
+		cfg.query_proof_rpc_parallel_tasks,
 	);
 
 	if cfg.sync_start_block.is_some() {
@@ -396,17 +503,27 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 	}
 
 	let static_config_params = StaticConfigParams {
-		block_confidence_treshold: cfg.confidence,
+		block_confidence_treshold: *cfg.confidence,
 		replication_factor: cfg.replication_factor,
 		query_timeout: cfg.query_timeout,
 		pruning_interval: cfg.store_pruning_interval,
 		telemetry_flush_interval: cfg.ot_flush_block_interval,
+		maintenance_timeout: Duration::from_secs(cfg.maintenance_timeout),
+		maintenance_interval: cfg.maintenance_interval,
+		peer_count_threshold: cfg.peer_count_threshold,
+		bootstraps: cfg.bootstraps.iter().map(Into::into).collect(),
+		bootstrap_check_interval: cfg.bootstrap_check_interval,
 	};
 
+	// Maintenance events are used by library consumers, binary doesn't subscribe to them
+	let (maintenance_event_tx, _) = broadcast::channel::<MaintenanceEvent>(1 << 7);
 	tokio::task::spawn(shutdown.with_cancel(avail_light::maintenance::run(
 		p2p_client.clone(),
-		ot_metrics.clone(),
+		rpc_client.header_cache(),
+		rpc_client.circuit_breakers(),
+		metrics.clone(),
 		block_rx,
+		maintenance_event_tx,
 		static_config_params,
 		shutdown.clone(),
 	)));
@@ -423,28 +540,36 @@ async fn run(shutdown: Controller<String>) -> Result<()> {
 			fat_client,
 			db.clone(),
 			(&cfg).into(),
-			ot_metrics.clone(),
+			metrics.clone(),
 			channels,
 			partition,
 			shutdown.clone(),
 		)));
 	} else {
-		let light_network_client = network::new(p2p_client, rpc_client, pp, cfg.disable_rpc);
+		let light_network_client = network::new(
+			p2p_client,
+			rpc_client,
+			pp,
+			cfg.disable_rpc,
+			cfg.cell_fetch_strategy,
+			cfg.max_cells_per_rpc(),
+			cfg.query_proof_rpc_parallel_tasks,
+		);
 
 		tokio::task::spawn(shutdown.with_cancel(avail_light::light_client::run(
 			db.clone(),
 			light_network_client,
 			(&cfg).into(),
-			ot_metrics.clone(),
+			metrics.clone(),
 			state.clone(),
 			channels,
 			shutdown.clone(),
 		)));
 	}
 
-	ot_metrics.count(MetricCounter::Starts).await;
+	metrics.count_or_warn(MetricCounter::Starts).await;
 
-	Ok(())
+	Ok(Startup::Running)
 }
 
 fn construct_multiaddress(is_websocket: bool, port: u16) -> Multiaddr {
@@ -541,9 +666,17 @@ pub async fn main() -> Result<()> {
 	// spawn a task to watch for ctrl-c signals from user to trigger the shutdown
 	tokio::spawn(shutdown.with_trigger("user signaled shutdown".to_string(), user_signal()));
 
-	if let Err(error) = run(shutdown.clone()).await {
-		error!("{error:#}");
-		return Err(error.wrap_err("Starting Light Client failed"));
+	match run(shutdown.clone()).await {
+		Ok(Startup::Running) => {},
+		Ok(Startup::Completed(reason)) => {
+			let _ = shutdown.trigger_shutdown(reason);
+			shutdown.completed_shutdown().await;
+			return Ok(());
+		},
+		Err(error) => {
+			error!("{error:#}");
+			return Err(error.wrap_err("Starting Light Client failed"));
+		},
 	};
 
 	let reason = shutdown.completed_shutdown().await;
@@ -4,6 +4,12 @@
 /// while all subsequent versions should be for backward compatibility/fallback/future-proofing versions.
 pub const EXPECTED_SYSTEM_VERSION: &[&str] = &["2.1"];
 
+/// Maximum number of rows in the (non-extended) Kate matrix supported by the protocol.
+pub const MAX_BLOCK_ROWS: u16 = 1024;
+
+/// Maximum number of columns in the Kate matrix supported by the protocol.
+pub const MAX_BLOCK_COLUMNS: u16 = 256;
+
 #[derive(Clone)]
 pub struct ExpectedNodeVariant {
 	pub system_version: &'static [&'static str],
@@ -3,7 +3,7 @@ use crate::{
 		p2p::Client,
 		rpc::{self, Event},
 	},
-	telemetry::{MetricValue, Metrics},
+	telemetry::{MetricValue, Metrics, MetricsExt},
 	types::{self, block_matrix_partition_format, Delay},
 };
 use kate_recovery::matrix::Partition;
@@ -86,8 +86,8 @@ pub async fn run(
 		if let Some(seconds) = delay.sleep_duration(received_at) {
 			info!("Sleeping for {seconds:?} seconds");
 			tokio::time::sleep(seconds).await;
-			let _ = metrics
-				.record(MetricValue::CrawlBlockDelay(seconds.as_secs() as f64))
+			metrics
+				.record_or_warn(MetricValue::CrawlBlockDelay(seconds.as_secs() as f64))
 				.await;
 		}
 		let block_number = block.block_num;
@@ -114,8 +114,8 @@ pub async fn run(
 				block_number,
 				partition, success_rate, total, fetched, "Fetched block cells",
 			);
-			let _ = metrics
-				.record(MetricValue::CrawlCellsSuccessRate(success_rate))
+			metrics
+				.record_or_warn(MetricValue::CrawlCellsSuccessRate(success_rate))
 				.await;
 		}
 
@@ -136,8 +136,8 @@ pub async fn run(
 				block_number,
 				success_rate, total, fetched, "Fetched block rows"
 			);
-			let _ = metrics
-				.record(MetricValue::CrawlRowsSuccessRate(success_rate))
+			metrics
+				.record_or_warn(MetricValue::CrawlRowsSuccessRate(success_rate))
 				.await;
 		}
 
@@ -2,6 +2,7 @@ use codec::{Decode, Encode};
 use color_eyre::eyre::Result;
 use serde::{Deserialize, Serialize};
 use sp_core::ed25519;
+use std::ops::RangeInclusive;
 
 pub mod rocks_db;
 
@@ -26,6 +27,25 @@ pub trait Database {
 
 	/// Deletes value from the database for the given key.
 	fn delete(&self, key: Key) -> Result<()>;
+
+	/// Returns ranges of blocks with stored verified cell count, in ascending order.
+	/// Only keys are scanned, stored values are not decoded.
+	fn verified_ranges(&self) -> Result<Vec<RangeInclusive<u32>>>;
+}
+
+/// Merges ascending block numbers into ranges of consecutive blocks
+pub fn block_ranges(block_numbers: impl IntoIterator<Item = u32>) -> Vec<RangeInclusive<u32>> {
+	let mut ranges: Vec<RangeInclusive<u32>> = vec![];
+	for block_number in block_numbers {
+		match ranges.last_mut() {
+			Some(range) if range.end().checked_add(1) == Some(block_number) => {
+				*range = *range.start()..=block_number;
+			},
+			Some(range) if range.contains(&block_number) => (),
+			_ => ranges.push(block_number..=block_number),
+		}
+	}
+	ranges
 }
 
 /// Column family for confidence factor
@@ -60,3 +80,34 @@ pub struct FinalitySyncCheckpoint {
 	pub set_id: u64,
 	pub validator_set: Vec<ed25519::Public>,
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{block_ranges, mem_db::MemoryDB, Database, Key};
+
+	#[test]
+	fn test_block_ranges() {
+		assert!(block_ranges([]).is_empty());
+		assert_eq!(
+			block_ranges([1, 2, 3, 5, 7, 8, 8, 9]),
+			vec![1..=3, 5..=5, 7..=9]
+		);
+		assert_eq!(
+			block_ranges([u32::MAX - 1, u32::MAX]),
+			vec![u32::MAX - 1..=u32::MAX]
+		);
+	}
+
+	#[test]
+	fn test_verified_ranges() {
+		let db = MemoryDB::default();
+		for block_number in [12, 3, 1, 2, 10, 11] {
+			db.put(Key::VerifiedCellCount(block_number), 8u32).unwrap();
+		}
+		// Other keys are not included
+		db.put(Key::AppData(1, 5), vec![1u8]).unwrap();
+		db.put(Key::BlockHeader(6), 1u32).unwrap();
+
+		assert_eq!(db.verified_ranges().unwrap(), vec![1..=3, 10..=12]);
+	}
+}
@@ -1,10 +1,12 @@
 use crate::data::{
-	Database, Key, APP_DATA_CF, BLOCK_HEADER_CF, CONFIDENCE_FACTOR_CF, FINALITY_SYNC_CHECKPOINT_KEY,
+	block_ranges, Database, Key, APP_DATA_CF, BLOCK_HEADER_CF, CONFIDENCE_FACTOR_CF,
+	FINALITY_SYNC_CHECKPOINT_KEY,
 };
 use color_eyre::eyre::{eyre, Result};
 use serde::{Deserialize, Serialize};
 use std::{
 	collections::HashMap,
+	ops::RangeInclusive,
 	sync::{Arc, RwLock},
 };
 
@@ -51,6 +53,17 @@ impl Database for MemoryDB {
 		map.remove(&key.into());
 		Ok(())
 	}
+
+	fn verified_ranges(&self) -> Result<Vec<RangeInclusive<u32>>> {
+		let map = self.map.read().expect("Lock acquired");
+		let prefix = format!("{CONFIDENCE_FACTOR_CF}:");
+		let mut block_numbers = map
+			.keys()
+			.filter_map(|HashMapKey(key)| key.strip_prefix(&prefix)?.parse::<u32>().ok())
+			.collect::<Vec<_>>();
+		block_numbers.sort_unstable();
+		Ok(block_ranges(block_numbers))
+	}
 }
 
 impl From<Key> for HashMapKey {
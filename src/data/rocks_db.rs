@@ -7,9 +7,9 @@ use crate::{
 };
 use codec::{Decode, Encode};
 use color_eyre::eyre::{eyre, Context, Result};
-use rocksdb::{ColumnFamilyDescriptor, Options};
+use rocksdb::{ColumnFamilyDescriptor, IteratorMode, Options};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{ops::RangeInclusive, sync::Arc};
 
 #[derive(Clone)]
 pub struct RocksDB {
@@ -134,4 +134,25 @@ impl data::Database for RocksDB {
 			.delete_cf(&cf_handle, key)
 			.wrap_err("Delete operation with Column Family failed on RocksDB")
 	}
+
+	fn verified_ranges(&self) -> Result<Vec<RangeInclusive<u32>>> {
+		let cf_handle = self
+			.db
+			.cf_handle(CONFIDENCE_FACTOR_CF)
+			.ok_or_else(|| eyre!("Couldn't get Column Family handle from RocksDB"))?;
+
+		// Block numbers are stored as big endian bytes, so keys are iterated in ascending order
+		let block_numbers =
+			self.db
+				.iterator_cf(&cf_handle, IteratorMode::Start)
+				.map(|item| -> Result<u32> {
+					let (key, _) = item.wrap_err("Iteration failed on RocksDB")?;
+					let bytes = key[..]
+						.try_into()
+						.map_err(|_| eyre!("Invalid verified cell count key"))?;
+					Ok(u32::from_be_bytes(bytes))
+				});
+
+		itertools::process_results(block_numbers, data::block_ranges)
+	}
 }
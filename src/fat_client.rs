@@ -11,7 +11,6 @@
 
 use async_trait::async_trait;
 use avail_subxt::{primitives::Header, utils::H256};
-use codec::Encode;
 use color_eyre::{eyre::WrapErr, Result};
 use futures::future::join_all;
 use kate_recovery::{
@@ -20,7 +19,6 @@ use kate_recovery::{
 };
 use kate_recovery::{data::Cell, matrix::RowIndex};
 use mockall::automock;
-use sp_core::blake2_256;
 use std::{sync::Arc, time::Instant};
 use tracing::{debug, error, info, warn};
 
@@ -31,9 +29,9 @@ use crate::{
 		rpc::{Client as RpcClient, Event},
 	},
 	shutdown::Controller,
-	telemetry::{MetricCounter, MetricValue, Metrics},
+	telemetry::{MetricCounter, MetricValue, Metrics, MetricsExt},
 	types::{BlockVerified, ClientChannels, FatClientConfig},
-	utils::extract_kate,
+	utils::{extract_kate, HeaderExt},
 };
 
 #[async_trait]
@@ -68,7 +66,10 @@ impl Client for FatClient {
 	}
 
 	async fn get_kate_proof(&self, hash: H256, positions: &[Position]) -> Result<Vec<Cell>> {
-		self.rpc_client.request_kate_proof(hash, positions).await
+		self.rpc_client
+			.request_kate_proof(hash, positions)
+			.await
+			.map_err(Into::into)
 	}
 }
 
@@ -81,13 +82,13 @@ pub async fn process_block(
 	received_at: Instant,
 	partition: Partition,
 ) -> Result<()> {
-	metrics.count(MetricCounter::SessionBlocks).await;
+	metrics.count_or_warn(MetricCounter::SessionBlocks).await;
 	metrics
-		.record(MetricValue::BlockHeight(header.number))
+		.record_or_warn(MetricValue::BlockHeight(header.number))
 		.await;
 
 	let block_number = header.number;
-	let header_hash: H256 = Encode::using_encoded(header, blake2_256).into();
+	let header_hash = header.hash();
 	let block_delay = received_at.elapsed().as_secs();
 	info!(block_number, block_delay, "Processing finalized block",);
 
@@ -165,8 +166,8 @@ pub async fn process_block(
 		"Partition cells received from RPC",
 	);
 	metrics
-		.record(MetricValue::RPCCallDuration(
-			partition_rpc_retrieve_time_elapsed.as_secs_f64(),
+		.record_or_warn(MetricValue::RPCCallDuration(
+			partition_rpc_retrieve_time_elapsed,
 		))
 		.await;
 
@@ -225,7 +226,7 @@ pub async fn run(
 
 		if let Some(seconds) = cfg.block_processing_delay.sleep_duration(received_at) {
 			metrics
-				.record(MetricValue::BlockProcessingDelay(seconds.as_secs_f64()))
+				.record_or_warn(MetricValue::BlockProcessingDelay(seconds.as_secs_f64()))
 				.await;
 			info!("Sleeping for {seconds:?} seconds");
 			tokio::time::sleep(seconds).await;
@@ -377,14 +378,10 @@ mod tests {
 			.expect_insert_cells_into_dht()
 			.returning(|_, _| Box::pin(async move { Ok(()) }));
 
-		let mut mock_metrics = telemetry::MockMetrics::new();
-		mock_metrics.expect_count().returning(|_| ());
-		mock_metrics.expect_record().returning(|_| ());
-
 		process_block(
 			&mock_client,
 			db,
-			&Arc::new(mock_metrics),
+			&Arc::new(telemetry::noop::Metrics),
 			&FatClientConfig::from(&RuntimeConfig::default()),
 			&default_header(),
 			Instant::now(),
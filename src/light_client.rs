@@ -17,16 +17,14 @@
 //! In case delay is configured, block processing is delayed for configured time.
 //! In case RPC is disabled, RPC calls will be skipped.
 
-use avail_subxt::{primitives::Header, utils::H256};
-use codec::Encode;
+use avail_subxt::primitives::Header;
 use color_eyre::{eyre::WrapErr, Result};
 use kate_recovery::{commitments, matrix::Dimensions};
-use sp_core::blake2_256;
 use std::{
 	sync::{Arc, Mutex},
 	time::Instant,
 };
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 use crate::{
 	data::{Database, Key},
@@ -35,9 +33,11 @@ use crate::{
 		rpc::{self, Event},
 	},
 	shutdown::Controller,
-	telemetry::{MetricCounter, MetricValue, Metrics},
-	types::{self, ClientChannels, LightClientConfig, OptionBlockRange, State},
-	utils::{calculate_confidence, extract_kate},
+	telemetry::{MetricCounter, MetricValue, Metrics, MetricsExt},
+	types::{
+		self, BlockVerificationResult, ClientChannels, LightClientConfig, OptionBlockRange, State,
+	},
+	utils::{calculate_confidence, extract_kate, HeaderExt},
 };
 
 pub async fn process_block(
@@ -48,14 +48,15 @@ pub async fn process_block(
 	header: Header,
 	received_at: Instant,
 	state: Arc<Mutex<State>>,
-) -> Result<Option<f64>> {
-	metrics.count(MetricCounter::SessionBlocks).await;
+) -> Result<Option<BlockVerificationResult>> {
+	let start_time = Instant::now();
+	metrics.count_or_warn(MetricCounter::SessionBlocks).await;
 	metrics
-		.record(MetricValue::BlockHeight(header.number))
+		.record_or_warn(MetricValue::BlockHeight(header.number))
 		.await;
 
 	let block_number = header.number;
-	let header_hash: H256 = Encode::using_encoded(&header, blake2_256).into();
+	let header_hash = header.hash();
 
 	info!(
 		{ block_number, block_delay = received_at.elapsed().as_secs()},
@@ -88,7 +89,7 @@ pub async fn process_block(
 			}
 
 			let commitments = commitments::from_slice(&commitment)?;
-			let cell_count = rpc::cell_count_for_confidence(cfg.confidence);
+			let cell_count = cfg.confidence_calculator.cells_needed(cfg.confidence);
 			let positions = rpc::generate_random_cells(dimensions, cell_count);
 			info!(
 				block_number,
@@ -108,30 +109,36 @@ pub async fn process_block(
 				.await?;
 
 			metrics
-				.record(MetricValue::DHTFetched(fetch_stats.dht_fetched))
+				.record_or_warn(MetricValue::DHTFetched(fetch_stats.dht_fetched))
 				.await;
 
 			metrics
-				.record(MetricValue::DHTFetchedPercentage(
+				.record_or_warn(MetricValue::DHTFetchedPercentage(
 					fetch_stats.dht_fetched_percentage,
 				))
 				.await;
 
 			metrics
-				.record(MetricValue::DHTFetchDuration(
+				.record_or_warn(MetricValue::DHTFetchDuration(
 					fetch_stats.dht_fetch_duration,
 				))
 				.await;
 
 			if let Some(rpc_fetched) = fetch_stats.rpc_fetched {
-				metrics.record(MetricValue::RPCFetched(rpc_fetched)).await;
+				metrics
+					.record_or_warn(MetricValue::RPCFetched(rpc_fetched))
+					.await;
 			}
 
 			if let Some(rpc_fetch_duration) = fetch_stats.rpc_fetch_duration {
 				metrics
-					.record(MetricValue::RPCFetchDuration(rpc_fetch_duration))
+					.record_or_warn(MetricValue::RPCFetchDuration(rpc_fetch_duration))
 					.await;
 			}
+
+			metrics
+				.record_or_warn(MetricValue::CellFetchSource(fetch_stats.source))
+				.await;
 			(positions.len(), fetched.len(), unfetched.len())
 		},
 	};
@@ -155,9 +162,19 @@ pub async fn process_block(
 		confidence
 	);
 	metrics
-		.record(MetricValue::BlockConfidence(confidence))
+		.record_or_warn(MetricValue::BlockConfidence(confidence))
 		.await;
 
+	let result = BlockVerificationResult {
+		block_number,
+		cells_sampled: required as u32,
+		cells_verified: verified as u32,
+		confidence,
+		mode: cfg.mode.clone(),
+		duration: start_time.elapsed(),
+	};
+	debug!("{result}");
+
 	// push latest mined block's header into column family specified
 	// for keeping block headers, to be used
 	// later for verifying DHT stored data
@@ -169,7 +186,7 @@ pub async fn process_block(
 	db.put(Key::BlockHeader(block_number), header)
 		.wrap_err("Light Client failed to store Block Header")?;
 
-	Ok(Some(confidence))
+	Ok(Some(result))
 }
 
 /// Runs light client.
@@ -209,7 +226,7 @@ pub async fn run(
 
 		if let Some(seconds) = cfg.block_processing_delay.sleep_duration(received_at) {
 			metrics
-				.record(MetricValue::BlockProcessingDelay(seconds.as_secs_f64()))
+				.record_or_warn(MetricValue::BlockProcessingDelay(seconds.as_secs_f64()))
 				.await;
 			info!("Sleeping for {seconds:?} seconds");
 			tokio::time::sleep(seconds).await;
@@ -225,8 +242,8 @@ pub async fn run(
 			state.clone(),
 		)
 		.await;
-		let confidence = match process_block_result {
-			Ok(confidence) => confidence,
+		let verification = match process_block_result {
+			Ok(result) => result,
 			Err(error) => {
 				error!("Cannot process block: {error}");
 				let _ = shutdown.trigger_shutdown(format!("Cannot process block: {error:#}"));
@@ -234,10 +251,12 @@ pub async fn run(
 			},
 		};
 
-		let Ok(client_msg) = types::BlockVerified::try_from((header, confidence)) else {
+		let confidence = verification.as_ref().map(|result| result.confidence);
+		let Ok(mut client_msg) = types::BlockVerified::try_from((header, confidence)) else {
 			error!("Cannot create message from header");
 			continue;
 		};
+		client_msg.verification = verification;
 
 		// notify dht-based application client
 		// that newly mined block has been received
@@ -255,9 +274,11 @@ mod tests {
 	use super::*;
 	use crate::{
 		data::mem_db,
-		network::rpc::{cell_count_for_confidence, CELL_COUNT_99_99},
+		network::rpc::{
+			cell_count_for_confidence, confidence_for_cell_count, CELL_COUNT_99_99, MAX_CONFIDENCE,
+		},
 		telemetry,
-		types::RuntimeConfig,
+		types::{CellFetchSource, RuntimeConfig},
 	};
 	use avail_subxt::{
 		api::runtime_types::avail_core::{
@@ -269,6 +290,7 @@ mod tests {
 	};
 	use hex_literal::hex;
 	use kate_recovery::{data::Cell, matrix::Position};
+	use proptest::{prelude::any, prop_assert, proptest};
 	use test_case::test_case;
 
 	#[test_case(99.9 => 10)]
@@ -279,10 +301,53 @@ mod tests {
 	#[test_case(49.0 => 8)]
 	#[test_case(50.0 => 1)]
 	#[test_case(50.1 => 2)]
+	#[test_case(92.0 => 4)]
+	#[test_case(99.0 => 7)]
+	#[test_case(0.0 => 8 ; "zero falls back to default")]
+	#[test_case(-10.0 => 8 ; "negative falls back to default")]
 	fn test_cell_count_for_confidence(confidence: f64) -> u32 {
 		cell_count_for_confidence(confidence)
 	}
 
+	proptest! {
+		#[test]
+		fn cell_count_for_confidence_is_bounded(confidence in any::<f64>()) {
+			let cell_count = cell_count_for_confidence(confidence);
+			prop_assert!((1..=CELL_COUNT_99_99).contains(&cell_count));
+		}
+	}
+
+	#[test_case(0 => 0.0)]
+	#[test_case(1 => 50.0)]
+	#[test_case(2 => 75.0)]
+	#[test_case(10 => 99.90234375)]
+	#[test_case(CELL_COUNT_99_99 => MAX_CONFIDENCE ; "capped at max confidence")]
+	#[test_case(54 => MAX_CONFIDENCE ; "large cell count")]
+	#[test_case(u32::MAX => MAX_CONFIDENCE ; "maximum cell count")]
+	fn test_confidence_for_cell_count(cell_count: u32) -> f64 {
+		confidence_for_cell_count(cell_count)
+	}
+
+	proptest! {
+		#[test]
+		fn confidence_for_cell_count_is_below_100(cell_count in any::<u32>()) {
+			let confidence = confidence_for_cell_count(cell_count);
+			prop_assert!((0.0..100.0).contains(&confidence));
+			prop_assert!(cell_count == 0 || confidence >= 50.0);
+		}
+	}
+
+	#[test]
+	fn test_confidence_for_cell_count_inverse() {
+		for cell_count in 1..=20 {
+			let confidence = confidence_for_cell_count(cell_count);
+			assert!((50.0..100.0).contains(&confidence));
+			// Cell count is capped for confidence above 99.99
+			let expected = cell_count.min(CELL_COUNT_99_99);
+			assert_eq!(cell_count_for_confidence(confidence), expected);
+		}
+	}
+
 	#[tokio::test]
 	async fn test_process_block_with_rpc() {
 		let mut mock_network_client = network::MockClient::new();
@@ -343,17 +408,15 @@ mod tests {
 					fetched.len(),
 					Duration::from_secs(0),
 					None,
+					CellFetchSource::DHT,
 				);
 				Box::pin(async move { Ok((fetched, unfetched, stats)) })
 			});
 
-		let mut mock_metrics = telemetry::MockMetrics::new();
-		mock_metrics.expect_count().returning(|_| ());
-		mock_metrics.expect_record().returning(|_| ());
 		process_block(
 			db,
 			&mock_network_client,
-			&Arc::new(mock_metrics),
+			&Arc::new(telemetry::noop::Metrics),
 			&cfg,
 			header,
 			recv,
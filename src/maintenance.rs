@@ -1,30 +1,125 @@
 use color_eyre::{eyre::WrapErr, Result};
-use std::sync::Arc;
-use tokio::sync::broadcast;
-use tracing::{debug, error, info};
+use libp2p::{Multiaddr, PeerId};
+use std::{num::NonZeroU32, sync::Arc, time::Duration};
+use tokio::{sync::broadcast, time::timeout};
+use tracing::{debug, error, info, warn};
 
 use crate::{
-	network::p2p::Client as P2pClient,
+	network::{
+		p2p::Client as P2pClient,
+		rpc::{CircuitBreakers, HeaderCache},
+	},
 	shutdown::Controller,
-	telemetry::{MetricValue, Metrics},
+	telemetry::{MetricValue, Metrics, MetricsExt},
 	types::BlockVerified,
 };
 
-#[derive(Clone, Copy)]
+/// Events emitted after each completed maintenance pass
+#[derive(Clone, Debug, PartialEq)]
+pub enum MaintenanceEvent {
+	KademliaShrank {
+		block_number: u32,
+		peers_removed: usize,
+	},
+	MetricsRecorded {
+		block_number: u32,
+	},
+}
+
+#[derive(Clone)]
 pub struct StaticConfigParams {
 	pub block_confidence_treshold: f64,
 	pub replication_factor: u16,
 	pub query_timeout: u32,
 	pub pruning_interval: u32,
 	pub telemetry_flush_interval: u32,
+	pub maintenance_timeout: Duration,
+	/// Interval of the Kademlia map shrinking, in blocks
+	pub maintenance_interval: NonZeroU32,
+	/// Number of DHT peers below which a warning is logged
+	pub peer_count_threshold: usize,
+	pub bootstraps: Vec<(PeerId, Multiaddr)>,
+	/// Interval of bootstrap peers connectivity check, in blocks (disabled if 0)
+	pub bootstrap_check_interval: u32,
+}
+
+/// Number of configured bootstrap peers which are currently connected
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BootstrapHealth {
+	pub connected: usize,
+	pub total: usize,
+}
+
+/// Checks how many of the given bootstrap peers are currently connected
+pub async fn check_bootstrap_connectivity(
+	p2p_client: &P2pClient,
+	bootstraps: &[(PeerId, Multiaddr)],
+) -> Result<BootstrapHealth> {
+	let connected_peers = p2p_client
+		.list_connected_peers()
+		.await
+		.wrap_err("Unable to list connected peers")?;
+
+	Ok(bootstrap_health(&connected_peers, bootstraps))
 }
 
+fn bootstrap_health(
+	connected_peers: &[String],
+	bootstraps: &[(PeerId, Multiaddr)],
+) -> BootstrapHealth {
+	let connected = bootstraps
+		.iter()
+		.filter(|(peer_id, _)| connected_peers.contains(&peer_id.to_string()))
+		.count();
+
+	BootstrapHealth {
+		connected,
+		total: bootstraps.len(),
+	}
+}
+
+/// Runs maintenance for the given block.
+/// Maintenance which doesn't complete within the configured timeout is abandoned,
+/// without returning an error, so a hanging P2P call cannot block the maintenance loop.
+/// Completed maintenance steps are published as [`MaintenanceEvent`]s.
+/// Returns the number of DHT peers, or `None` if maintenance timed out.
 pub async fn process_block(
 	block_number: u32,
 	p2p_client: &P2pClient,
-	static_config_params: StaticConfigParams,
+	header_cache: &HeaderCache,
+	circuit_breakers: &CircuitBreakers,
+	static_config_params: &StaticConfigParams,
 	metrics: &Arc<impl Metrics>,
-) -> Result<()> {
+	event_sender: &broadcast::Sender<MaintenanceEvent>,
+) -> Result<Option<usize>> {
+	let maintenance_timeout = static_config_params.maintenance_timeout;
+	let maintenance = maintain(
+		block_number,
+		p2p_client,
+		header_cache,
+		circuit_breakers,
+		static_config_params,
+		metrics,
+		event_sender,
+	);
+	match timeout(maintenance_timeout, maintenance).await {
+		Ok(result) => result.map(Some),
+		Err(_) => {
+			warn!(block_number, ?maintenance_timeout, "Maintenance timed out");
+			Ok(None)
+		},
+	}
+}
+
+async fn maintain(
+	block_number: u32,
+	p2p_client: &P2pClient,
+	header_cache: &HeaderCache,
+	circuit_breakers: &CircuitBreakers,
+	static_config_params: &StaticConfigParams,
+	metrics: &Arc<impl Metrics>,
+	event_sender: &broadcast::Sender<MaintenanceEvent>,
+) -> Result<usize> {
 	#[cfg(not(feature = "kademlia-rocksdb"))]
 	if block_number % static_config_params.pruning_interval == 0 {
 		info!(block_number, "Pruning...");
@@ -42,66 +137,516 @@ pub async fn process_block(
 		}
 	}
 
-	p2p_client
-		.shrink_kademlia_map()
-		.await
-		.wrap_err("Unable to perform Kademlia map shrink")?;
+	let check_interval = static_config_params.bootstrap_check_interval;
+	if check_interval > 0 && block_number % check_interval == 0 {
+		match check_bootstrap_connectivity(p2p_client, &static_config_params.bootstraps).await {
+			Ok(BootstrapHealth { connected, total }) => {
+				if connected == 0 && total > 0 {
+					warn!(block_number, total, "No bootstrap peers connected");
+				}
+				metrics
+					.record_or_warn(MetricValue::BootstrapConnected(connected))
+					.await;
+			},
+			Err(error) => error!(
+				block_number,
+				"Bootstrap connectivity check failed: {error:#}"
+			),
+		}
+	}
 
-	let map_size = p2p_client
-		.get_kademlia_map_size()
-		.await
-		.wrap_err("Unable to get Kademlia map size")?;
+	if block_number % static_config_params.maintenance_interval.get() == 0 {
+		shrink_kademlia_map(block_number, p2p_client, metrics, event_sender).await?;
+	} else {
+		debug!(block_number, "Skipping Kademlia map shrink");
+	}
 
-	let peers_num = p2p_client.count_dht_entries().await?;
+	let peers_num = p2p_client
+		.count_dht_entries()
+		.await
+		.wrap_err("Unable to count DHT entries")?;
 	info!("Number of connected peers: {peers_num}");
 
 	let connected_peers = p2p_client.list_connected_peers().await?;
 	debug!("Connected peers: {:?}", connected_peers);
 
 	let peers_num_metric = MetricValue::DHTConnectedPeers(peers_num);
-	metrics.record(peers_num_metric).await;
+	metrics.record_or_warn(peers_num_metric).await;
 
 	metrics
-		.record(MetricValue::BlockConfidenceThreshold(
+		.record_or_warn(MetricValue::BlockConfidenceThreshold(
 			static_config_params.block_confidence_treshold,
 		))
 		.await;
 	metrics
-		.record(MetricValue::DHTReplicationFactor(
+		.record_or_warn(MetricValue::DHTReplicationFactor(
 			static_config_params.replication_factor,
 		))
 		.await;
 	metrics
-		.record(MetricValue::DHTQueryTimeout(
+		.record_or_warn(MetricValue::DHTQueryTimeout(
 			static_config_params.query_timeout,
 		))
 		.await;
-	metrics.record(MetricValue::Up()).await;
+	if let Some(hit_rate) = header_cache.hit_rate() {
+		metrics
+			.record_or_warn(MetricValue::RPCHeaderCacheHitRate(hit_rate))
+			.await;
+	}
+	metrics
+		.record_or_warn(MetricValue::RPCOpenCircuits(circuit_breakers.open_count()))
+		.await;
+	metrics.record_or_warn(MetricValue::Up()).await;
+	let _ = event_sender.send(MaintenanceEvent::MetricsRecorded { block_number });
+
+	info!(block_number, "Maintenance completed");
+	Ok(peers_num)
+}
+
+/// DHT peer count compared to the configured threshold
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PeerCountStatus {
+	Normal,
+	Low,
+	/// Peer count is not below the threshold anymore
+	Recovered,
+}
+
+impl PeerCountStatus {
+	fn new(peers_num: usize, threshold: usize, was_low: bool) -> Self {
+		match (peers_num < threshold, was_low) {
+			(true, _) => PeerCountStatus::Low,
+			(false, true) => PeerCountStatus::Recovered,
+			(false, false) => PeerCountStatus::Normal,
+		}
+	}
+}
+
+/// Checks the DHT peer count against the threshold, given the outcome of the previous check.
+/// Logs a warning while the peer count is low, and once when it recovers.
+/// Peer count below threshold metric is recorded only when the count drops or recovers.
+async fn check_peer_count(
+	peers_num: usize,
+	threshold: usize,
+	was_low: bool,
+	metrics: &Arc<impl Metrics>,
+) -> PeerCountStatus {
+	let status = PeerCountStatus::new(peers_num, threshold, was_low);
+	match status {
+		PeerCountStatus::Low => {
+			warn!(peers_num, threshold, "DHT peer count below threshold");
+			if !was_low {
+				metrics
+					.record_or_warn(MetricValue::PeerCountBelowThreshold(true))
+					.await;
+			}
+		},
+		PeerCountStatus::Recovered => {
+			info!(peers_num, threshold, "DHT peer count recovered");
+			metrics
+				.record_or_warn(MetricValue::PeerCountBelowThreshold(false))
+				.await;
+		},
+		PeerCountStatus::Normal => {},
+	}
+	status
+}
 
-	info!(block_number, map_size, "Maintenance completed");
+/// Shrinks Kademlia map, which is too expensive to be done on every block
+async fn shrink_kademlia_map(
+	block_number: u32,
+	p2p_client: &P2pClient,
+	metrics: &Arc<impl Metrics>,
+	event_sender: &broadcast::Sender<MaintenanceEvent>,
+) -> Result<()> {
+	let previous_map_size = p2p_client
+		.get_kademlia_map_size()
+		.await
+		.wrap_err("Unable to get Kademlia map size")?;
+
+	p2p_client
+		.shrink_kademlia_map()
+		.await
+		.wrap_err("Unable to perform Kademlia map shrink")?;
+
+	let map_size = p2p_client
+		.get_kademlia_map_size()
+		.await
+		.wrap_err("Unable to get Kademlia map size")?;
+
+	info!(block_number, map_size, "Kademlia map shrink completed");
+
+	// Sending fails only if there are no subscribers
+	let _ = event_sender.send(MaintenanceEvent::KademliaShrank {
+		block_number,
+		peers_removed: previous_map_size.saturating_sub(map_size),
+	});
+	metrics
+		.record_or_warn(MetricValue::MaintenanceLastRun(block_number))
+		.await;
 	Ok(())
 }
 
+/// Runs maintenance for each verified block, until the block channel is closed.
+/// Maintenance events are published to `event_sender` for library consumers,
+/// sending doesn't fail or block if there are no subscribers.
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
 	p2p_client: P2pClient,
+	header_cache: Arc<HeaderCache>,
+	circuit_breakers: Arc<CircuitBreakers>,
 	metrics: Arc<impl Metrics>,
 	mut block_receiver: broadcast::Receiver<BlockVerified>,
+	event_sender: broadcast::Sender<MaintenanceEvent>,
 	static_config_params: StaticConfigParams,
 	shutdown: Controller<String>,
 ) {
 	info!("Starting maintenance...");
 
+	let threshold = static_config_params.peer_count_threshold;
+	let mut peer_count_low = false;
 	loop {
 		let result = match block_receiver.recv().await {
 			Ok(block) => {
-				process_block(block.block_num, &p2p_client, static_config_params, &metrics).await
+				process_block(
+					block.block_num,
+					&p2p_client,
+					&header_cache,
+					&circuit_breakers,
+					&static_config_params,
+					&metrics,
+					&event_sender,
+				)
+				.await
 			},
 			Err(error) => Err(error.into()),
 		};
 
-		if let Err(error) = result {
-			let _ = shutdown.trigger_shutdown(format!("{error:#}"));
-			break;
+		match result {
+			Ok(Some(peers_num)) => {
+				let status = check_peer_count(peers_num, threshold, peer_count_low, &metrics).await;
+				peer_count_low = status == PeerCountStatus::Low;
+			},
+			// Previous outcome is kept if maintenance timed out
+			Ok(None) => {},
+			Err(error) => {
+				let _ = shutdown.trigger_shutdown(format!("{error:#}"));
+				break;
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		network::p2p::Command,
+		telemetry::MockMetrics,
+		types::{LibP2PConfig, RuntimeConfig},
+	};
+	use color_eyre::eyre::eyre;
+	use std::num::NonZeroUsize;
+	use test_case::test_case;
+	use tokio::sync::mpsc;
+
+	fn test_config_params() -> StaticConfigParams {
+		StaticConfigParams {
+			block_confidence_treshold: 99.9,
+			replication_factor: 5,
+			query_timeout: 10,
+			pruning_interval: 180,
+			telemetry_flush_interval: 15,
+			maintenance_timeout: Duration::from_secs(30),
+			maintenance_interval: NonZeroU32::new(1).unwrap(),
+			peer_count_threshold: 3,
+			bootstraps: vec![],
+			bootstrap_check_interval: 0,
+		}
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_process_block_timeout() {
+		// Commands are never processed, so every P2P call hangs
+		let (command_sender, _command_receiver) = mpsc::unbounded_channel();
+		let p2p_client = P2pClient::new(command_sender, 1, 3600);
+		let static_config_params = StaticConfigParams {
+			bootstrap_check_interval: 10,
+			..test_config_params()
+		};
+		let mut mock_metrics = MockMetrics::new();
+		mock_metrics.expect_record().never();
+		let (event_sender, mut event_receiver) = broadcast::channel(8);
+
+		let result = process_block(
+			1,
+			&p2p_client,
+			&HeaderCache::new(NonZeroUsize::new(1).unwrap()),
+			&CircuitBreakers::new(Default::default(), 1),
+			&static_config_params,
+			&Arc::new(mock_metrics),
+			&event_sender,
+		)
+		.await;
+		assert!(matches!(result, Ok(None)));
+		// Maintenance didn't complete, so no events are emitted
+		assert!(event_receiver.try_recv().is_err());
+	}
+
+	#[test_case(2, false, PeerCountStatus::Low, Some(true) ; "count drops below threshold")]
+	#[test_case(2, true, PeerCountStatus::Low, None ; "count stays below threshold")]
+	#[test_case(3, true, PeerCountStatus::Recovered, Some(false) ; "count recovers")]
+	#[test_case(3, false, PeerCountStatus::Normal, None ; "count stays above threshold")]
+	#[tokio::test]
+	async fn test_check_peer_count(
+		peers_num: usize,
+		was_low: bool,
+		expected_status: PeerCountStatus,
+		expected_metric: Option<bool>,
+	) {
+		let mut mock_metrics = MockMetrics::new();
+		match expected_metric {
+			Some(expected) => {
+				mock_metrics
+					.expect_record()
+					.withf(move |value| {
+						matches!(value, MetricValue::PeerCountBelowThreshold(below) if *below == expected)
+					})
+					.times(1)
+					.returning(|_| Ok(()));
+			},
+			None => {
+				mock_metrics.expect_record().never();
+			},
+		}
+		let metrics = Arc::new(mock_metrics);
+		let status = check_peer_count(peers_num, 3, was_low, &metrics).await;
+		assert_eq!(status, expected_status);
+	}
+
+	#[tokio::test]
+	async fn test_check_peer_count_transitions() {
+		let recorded = Arc::new(std::sync::Mutex::new(vec![]));
+		let mut mock_metrics = MockMetrics::new();
+		let recorded_values = recorded.clone();
+		mock_metrics.expect_record().returning(move |value| {
+			if let MetricValue::PeerCountBelowThreshold(below) = value {
+				recorded_values.lock().unwrap().push(below);
+			}
+			Ok(())
+		});
+		let metrics = Arc::new(mock_metrics);
+
+		let mut peer_count_low = false;
+		let mut statuses = vec![];
+		for peers_num in [5, 2, 1, 3, 4] {
+			let status = check_peer_count(peers_num, 3, peer_count_low, &metrics).await;
+			peer_count_low = status == PeerCountStatus::Low;
+			statuses.push(status);
+		}
+		// Drop and recovery are recorded only once
+		assert_eq!(*recorded.lock().unwrap(), vec![true, false]);
+		// Recovery is reported only once, after the count was low
+		assert_eq!(
+			statuses,
+			vec![
+				PeerCountStatus::Normal,
+				PeerCountStatus::Low,
+				PeerCountStatus::Low,
+				PeerCountStatus::Recovered,
+				PeerCountStatus::Normal,
+			]
+		);
+	}
+
+	/// Creates P2P client which fails every command, and static config with the given maintenance interval
+	fn failing_p2p_client(maintenance_interval: u32) -> (P2pClient, StaticConfigParams) {
+		let (command_sender, mut command_receiver) = mpsc::unbounded_channel();
+		let p2p_client = P2pClient::new(command_sender, 1, 3600);
+		tokio::spawn(async move {
+			while let Some(mut command) = command_receiver.recv().await {
+				command.abort(eyre!("P2P command failed"));
+			}
+		});
+		let static_config_params = StaticConfigParams {
+			maintenance_interval: NonZeroU32::new(maintenance_interval).unwrap(),
+			..test_config_params()
+		};
+		(p2p_client, static_config_params)
+	}
+
+	#[test_case(10, "Unable to get Kademlia map size" ; "kademlia map is shrunk")]
+	#[test_case(11, "Unable to count DHT entries" ; "kademlia map shrink is skipped")]
+	#[tokio::test]
+	async fn test_process_block_maintenance_interval(block_number: u32, expected_error: &str) {
+		let (p2p_client, static_config_params) = failing_p2p_client(10);
+		let mut mock_metrics = MockMetrics::new();
+		mock_metrics.expect_record().never();
+		let (event_sender, _) = broadcast::channel(8);
+
+		let error = process_block(
+			block_number,
+			&p2p_client,
+			&HeaderCache::new(NonZeroUsize::new(1).unwrap()),
+			&CircuitBreakers::new(Default::default(), 1),
+			&static_config_params,
+			&Arc::new(mock_metrics),
+			&event_sender,
+		)
+		.await
+		.unwrap_err();
+		// First failing step is reported
+		assert_eq!(error.to_string(), expected_error);
+	}
+
+	/// Creates P2P client backed by the event loop, which is not connected to any peer
+	#[cfg(not(feature = "kademlia-rocksdb"))]
+	async fn event_loop_p2p_client() -> P2pClient {
+		use crate::network::p2p::EventLoop;
+		use libp2p::identity::Keypair;
+
+		let cfg = LibP2PConfig::from(&RuntimeConfig::default());
+		let id_keys = Keypair::generate_ed25519();
+		let event_loop = EventLoop::new(cfg, &id_keys, false, false, Controller::new()).await;
+		let mut event_loop_metrics = MockMetrics::new();
+		event_loop_metrics.expect_count().returning(|_| Ok(()));
+		event_loop_metrics.expect_record().returning(|_| Ok(()));
+		let (command_sender, command_receiver) = mpsc::unbounded_channel();
+		tokio::spawn(event_loop.run(Arc::new(event_loop_metrics), command_receiver));
+		P2pClient::new(command_sender, 1, 3600)
+	}
+
+	fn bootstrap(peer_id: PeerId) -> (PeerId, Multiaddr) {
+		(peer_id, "/ip4/127.0.0.1/tcp/39000".parse().unwrap())
+	}
+
+	#[test]
+	fn test_bootstrap_health() {
+		let connected_peer = PeerId::random();
+		let bootstraps = [bootstrap(connected_peer), bootstrap(PeerId::random())];
+		let connected_peers = [PeerId::random(), connected_peer].map(|peer| peer.to_string());
+
+		let health = bootstrap_health(&connected_peers, &bootstraps);
+		assert_eq!((health.connected, health.total), (1, 2));
+		let health = bootstrap_health(&[], &bootstraps);
+		assert_eq!((health.connected, health.total), (0, 2));
+		let health = bootstrap_health(&connected_peers, &[]);
+		assert_eq!((health.connected, health.total), (0, 0));
+	}
+
+	#[cfg(not(feature = "kademlia-rocksdb"))]
+	#[tokio::test]
+	async fn test_check_bootstrap_connectivity() {
+		let p2p_client = event_loop_p2p_client().await;
+		let bootstraps = [bootstrap(PeerId::random()), bootstrap(PeerId::random())];
+
+		let health = check_bootstrap_connectivity(&p2p_client, &bootstraps)
+			.await
+			.unwrap();
+		assert_eq!(
+			health,
+			BootstrapHealth {
+				connected: 0,
+				total: 2
+			}
+		);
+	}
+
+	#[tokio::test]
+	async fn test_check_bootstrap_connectivity_error() {
+		let (p2p_client, _) = failing_p2p_client(10);
+		let bootstraps = [bootstrap(PeerId::random())];
+
+		let error = check_bootstrap_connectivity(&p2p_client, &bootstraps)
+			.await
+			.unwrap_err();
+		assert_eq!(error.to_string(), "Unable to list connected peers");
+	}
+
+	#[cfg(not(feature = "kademlia-rocksdb"))]
+	#[tokio::test]
+	async fn test_process_block_bootstrap_check() {
+		let p2p_client = event_loop_p2p_client().await;
+		let static_config_params = StaticConfigParams {
+			maintenance_interval: NonZeroU32::new(10).unwrap(),
+			peer_count_threshold: 0,
+			bootstraps: vec![bootstrap(PeerId::random())],
+			bootstrap_check_interval: 5,
+			..test_config_params()
+		};
+		let mut mock_metrics = MockMetrics::new();
+		// No bootstrap peers are connected
+		mock_metrics
+			.expect_record()
+			.withf(|value| matches!(value, MetricValue::BootstrapConnected(0)))
+			.times(1)
+			.returning(|_| Ok(()));
+		mock_metrics
+			.expect_record()
+			.withf(|value| !matches!(value, MetricValue::BootstrapConnected(_)))
+			.returning(|_| Ok(()));
+		let metrics = Arc::new(mock_metrics);
+		let (event_sender, _) = broadcast::channel(8);
+
+		// Check runs only on every fifth block
+		for block_number in [11, 15] {
+			process_block(
+				block_number,
+				&p2p_client,
+				&HeaderCache::new(NonZeroUsize::new(1).unwrap()),
+				&CircuitBreakers::new(Default::default(), 1),
+				&static_config_params,
+				&metrics,
+				&event_sender,
+			)
+			.await
+			.unwrap();
+		}
+	}
+
+	#[cfg(not(feature = "kademlia-rocksdb"))]
+	#[tokio::test]
+	async fn test_process_block_events() {
+		let p2p_client = event_loop_p2p_client().await;
+
+		let static_config_params = StaticConfigParams {
+			maintenance_interval: NonZeroU32::new(10).unwrap(),
+			peer_count_threshold: 0,
+			..test_config_params()
+		};
+		let mut mock_metrics = MockMetrics::new();
+		mock_metrics.expect_record().returning(|_| Ok(()));
+		let metrics = Arc::new(mock_metrics);
+		let (event_sender, mut event_receiver) = broadcast::channel(8);
+
+		for block_number in [10, 11] {
+			process_block(
+				block_number,
+				&p2p_client,
+				&HeaderCache::new(NonZeroUsize::new(1).unwrap()),
+				&CircuitBreakers::new(Default::default(), 1),
+				&static_config_params,
+				&metrics,
+				&event_sender,
+			)
+			.await
+			.unwrap();
+		}
+
+		let mut events = vec![];
+		while let Ok(event) = event_receiver.try_recv() {
+			events.push(event);
 		}
+		assert_eq!(
+			events,
+			vec![
+				MaintenanceEvent::KademliaShrank {
+					block_number: 10,
+					peers_removed: 0
+				},
+				MaintenanceEvent::MetricsRecorded { block_number: 10 },
+				MaintenanceEvent::MetricsRecorded { block_number: 11 },
+			]
+		);
 	}
 }
@@ -8,11 +8,14 @@ use kate_recovery::{
 };
 use mockall::automock;
 use sp_core::H256;
-use std::{sync::Arc, time::Duration};
+use std::{future::Future, sync::Arc, time::Duration};
 use tokio::time::Instant;
 use tracing::{debug, info};
 
-use crate::proof;
+use crate::{
+	proof,
+	types::{CellFetchSource, CellFetchStrategy},
+};
 
 pub mod p2p;
 pub mod rpc;
@@ -33,9 +36,10 @@ pub trait Client {
 pub struct FetchStats {
 	pub dht_fetched: f64,
 	pub dht_fetched_percentage: f64,
-	pub dht_fetch_duration: f64,
+	pub dht_fetch_duration: Duration,
 	pub rpc_fetched: Option<f64>,
-	pub rpc_fetch_duration: Option<f64>,
+	pub rpc_fetch_duration: Option<Duration>,
+	pub source: CellFetchSource,
 }
 
 type RPCFetchStats = (usize, Duration);
@@ -46,13 +50,15 @@ impl FetchStats {
 		dht_fetched: usize,
 		dht_fetch_duration: Duration,
 		rpc_fetch_stats: Option<RPCFetchStats>,
+		source: CellFetchSource,
 	) -> Self {
 		FetchStats {
 			dht_fetched: dht_fetched as f64,
 			dht_fetched_percentage: dht_fetched as f64 / total as f64,
-			dht_fetch_duration: dht_fetch_duration.as_secs_f64(),
+			dht_fetch_duration,
 			rpc_fetched: rpc_fetch_stats.map(|(rpc_fetched, _)| rpc_fetched as f64),
-			rpc_fetch_duration: rpc_fetch_stats.map(|(_, duration)| duration.as_secs_f64()),
+			rpc_fetch_duration: rpc_fetch_stats.map(|(_, duration)| duration),
+			source,
 		}
 	}
 }
@@ -62,6 +68,9 @@ struct DHTWithRPCFallbackClient {
 	rpc_client: rpc::Client,
 	pp: Arc<PublicParameters>,
 	disable_rpc: bool,
+	strategy: CellFetchStrategy,
+	max_cells_per_rpc: usize,
+	query_proof_rpc_parallel_tasks: usize,
 }
 
 type Commitments = [[u8; config::COMMITMENT_SIZE]];
@@ -83,7 +92,7 @@ impl DHTWithRPCFallbackClient {
 
 		let fetch_elapsed = begin.elapsed();
 
-		let (verified, mut unverified) = proof::verify(
+		let summary = proof::verify(
 			block_number,
 			dimensions,
 			&dht_fetched,
@@ -97,14 +106,14 @@ impl DHTWithRPCFallbackClient {
 			block_number,
 			cells_total = positions.len(),
 			cells_fetched = dht_fetched.len(),
-			cells_verified = verified.len(),
+			cells_verified = summary.verified,
 			fetch_elapsed = ?fetch_elapsed,
 			proof_verification_elapsed = ?(begin.elapsed() - fetch_elapsed),
 			"Cells fetched from DHT"
 		);
 
-		dht_fetched.retain(|cell| verified.contains(&cell.position));
-		unfetched.append(&mut unverified);
+		dht_fetched.retain(|cell| !summary.failed.contains(&cell.position));
+		unfetched.extend(summary.failed);
 
 		Ok((dht_fetched, unfetched, fetch_elapsed))
 	}
@@ -119,14 +128,17 @@ impl DHTWithRPCFallbackClient {
 	) -> Result<(Vec<Cell>, Vec<Position>, Duration)> {
 		let begin = Instant::now();
 
-		let mut fetched = self
-			.rpc_client
-			.request_kate_proof(block_hash, positions)
-			.await?;
+		let mut fetched = rpc::get_kate_proof_chunked(
+			positions,
+			self.max_cells_per_rpc,
+			self.query_proof_rpc_parallel_tasks,
+			|positions| self.rpc_client.request_kate_proof(block_hash, positions),
+		)
+		.await?;
 
 		let fetch_elapsed = begin.elapsed();
 
-		let (verified, unverified) = proof::verify(
+		let summary = proof::verify(
 			block_number,
 			dimensions,
 			&fetched,
@@ -140,34 +152,34 @@ impl DHTWithRPCFallbackClient {
 			block_number,
 			cells_total = positions.len(),
 			cells_fetched = fetched.len(),
-			cells_verified = verified.len(),
+			cells_verified = summary.verified,
 			fetch_elapsed = ?fetch_elapsed,
 			proof_verification_elapsed = ?(begin.elapsed() - fetch_elapsed),
 			"Cells fetched from RPC"
 		);
 
-		fetched.retain(|cell| verified.contains(&cell.position));
-		Ok((fetched, unverified, fetch_elapsed))
+		fetched.retain(|cell| !summary.failed.contains(&cell.position));
+		Ok((fetched, summary.failed, fetch_elapsed))
 	}
-}
 
-#[async_trait]
-impl Client for DHTWithRPCFallbackClient {
-	async fn fetch_verified(
+	/// Fetches cells missing from DHT via RPC, and inserts them into the DHT
+	async fn complete_from_rpc(
 		&self,
 		block_number: u32,
 		block_hash: H256,
 		dimensions: Dimensions,
 		commitments: &Commitments,
 		positions: &[Position],
+		(dht_fetched, unfetched, dht_fetch_duration): (Vec<Cell>, Vec<Position>, Duration),
 	) -> Result<(Vec<Cell>, Vec<Position>, FetchStats)> {
-		let (dht_fetched, unfetched, dht_fetch_duration) = self
-			.fetch_verified_from_dht(block_number, dimensions, commitments, positions)
-			.await?;
-
 		if self.disable_rpc {
-			let stats =
-				FetchStats::new(positions.len(), dht_fetched.len(), dht_fetch_duration, None);
+			let stats = FetchStats::new(
+				positions.len(),
+				dht_fetched.len(),
+				dht_fetch_duration,
+				None,
+				CellFetchSource::DHT,
+			);
 			return Ok((dht_fetched, unfetched, stats));
 		};
 
@@ -181,19 +193,14 @@ impl Client for DHTWithRPCFallbackClient {
 			)
 			.await?;
 
-		if let Err(error) = self
-			.p2p_client
-			.insert_cells_into_dht(block_number, rpc_fetched.clone())
-			.await
-		{
-			debug!("Error inserting cells into DHT: {error}");
-		}
+		self.insert_into_dht(block_number, &rpc_fetched).await;
 
 		let stats = FetchStats::new(
 			positions.len(),
 			dht_fetched.len(),
 			dht_fetch_duration,
 			Some((rpc_fetched.len(), rpc_fetch_duration)),
+			CellFetchSource::DHT,
 		);
 
 		let mut fetched = vec![];
@@ -202,6 +209,132 @@ impl Client for DHTWithRPCFallbackClient {
 
 		Ok((fetched, unfetched, stats))
 	}
+
+	/// Inserts cells fetched via RPC into the DHT, and fetches cells missing from RPC via DHT
+	async fn complete_from_dht(
+		&self,
+		block_number: u32,
+		dimensions: Dimensions,
+		commitments: &Commitments,
+		positions: &[Position],
+		(rpc_fetched, unfetched, rpc_fetch_duration): (Vec<Cell>, Vec<Position>, Duration),
+	) -> Result<(Vec<Cell>, Vec<Position>, FetchStats)> {
+		self.insert_into_dht(block_number, &rpc_fetched).await;
+
+		let (dht_fetched, unfetched, dht_fetch_duration) = self
+			.fetch_verified_from_dht(block_number, dimensions, commitments, &unfetched)
+			.await?;
+
+		let stats = FetchStats::new(
+			positions.len(),
+			dht_fetched.len(),
+			dht_fetch_duration,
+			Some((rpc_fetched.len(), rpc_fetch_duration)),
+			CellFetchSource::RPC,
+		);
+
+		let mut fetched = vec![];
+		fetched.extend(rpc_fetched);
+		fetched.extend(dht_fetched);
+
+		Ok((fetched, unfetched, stats))
+	}
+
+	async fn insert_into_dht(&self, block_number: u32, cells: &[Cell]) {
+		if let Err(error) = self
+			.p2p_client
+			.insert_cells_into_dht(block_number, cells.to_vec())
+			.await
+		{
+			debug!("Error inserting cells into DHT: {error}");
+		}
+	}
+}
+
+#[async_trait]
+impl Client for DHTWithRPCFallbackClient {
+	async fn fetch_verified(
+		&self,
+		block_number: u32,
+		block_hash: H256,
+		dimensions: Dimensions,
+		commitments: &Commitments,
+		positions: &[Position],
+	) -> Result<(Vec<Cell>, Vec<Position>, FetchStats)> {
+		let strategy = match self.disable_rpc {
+			true => CellFetchStrategy::DHTFirst,
+			false => self.strategy,
+		};
+
+		let dht_fetch =
+			self.fetch_verified_from_dht(block_number, dimensions, commitments, positions);
+		let rpc_fetch = self.fetch_verified_from_rpc(
+			block_number,
+			block_hash,
+			dimensions,
+			commitments,
+			positions,
+		);
+
+		match fetch_first(strategy, dht_fetch, rpc_fetch).await? {
+			FirstFetch::DHT(dht_result) => {
+				self.complete_from_rpc(
+					block_number,
+					block_hash,
+					dimensions,
+					commitments,
+					positions,
+					dht_result,
+				)
+				.await
+			},
+			FirstFetch::RPC(rpc_result) => {
+				self.complete_from_dht(block_number, dimensions, commitments, positions, rpc_result)
+					.await
+			},
+		}
+	}
+}
+
+/// Result of the fetch which is completed first, depending on the cell fetch strategy
+#[derive(Debug, PartialEq)]
+enum FirstFetch<T> {
+	DHT(T),
+	RPC(T),
+}
+
+/// Runs the DHT and RPC fetches according to the given strategy, and returns the first result.
+/// With the parallel strategy, fetches run concurrently and the slower one is dropped (cancelled),
+/// unless the faster one fails, in which case the result of the other one is awaited.
+/// Fetches which are not needed by the strategy are never polled.
+async fn fetch_first<T>(
+	strategy: CellFetchStrategy,
+	dht_fetch: impl Future<Output = Result<T>>,
+	rpc_fetch: impl Future<Output = Result<T>>,
+) -> Result<FirstFetch<T>> {
+	match strategy {
+		CellFetchStrategy::DHTFirst => dht_fetch.await.map(FirstFetch::DHT),
+		CellFetchStrategy::RPCFirst => rpc_fetch.await.map(FirstFetch::RPC),
+		CellFetchStrategy::Parallel => {
+			tokio::pin!(dht_fetch, rpc_fetch);
+			tokio::select! {
+				dht_result = &mut dht_fetch => match dht_result {
+					Ok(dht_result) => Ok(FirstFetch::DHT(dht_result)),
+					Err(error) => {
+						debug!("Fetching cells from DHT failed, waiting for RPC: {error:#}");
+						rpc_fetch.await.map(FirstFetch::RPC)
+					},
+				},
+				rpc_result = &mut rpc_fetch => match rpc_result {
+					Ok(rpc_result) => Ok(FirstFetch::RPC(rpc_result)),
+					Err(error) => {
+						debug!("Fetching cells from RPC failed, waiting for DHT: {error:#}");
+						dht_fetch.await.map(FirstFetch::DHT)
+					},
+				},
+			}
+		},
+	}
 }
 
 pub fn new(
@@ -209,11 +342,98 @@ pub fn new(
 	rpc_client: rpc::Client,
 	pp: Arc<PublicParameters>,
 	disable_rpc: bool,
+	strategy: CellFetchStrategy,
+	max_cells_per_rpc: usize,
+	query_proof_rpc_parallel_tasks: usize,
 ) -> impl Client {
 	DHTWithRPCFallbackClient {
 		p2p_client,
 		rpc_client,
 		pp,
 		disable_rpc,
+		strategy,
+		max_cells_per_rpc,
+		query_proof_rpc_parallel_tasks,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{fetch_first, FirstFetch};
+	use crate::types::CellFetchStrategy;
+	use color_eyre::{eyre::eyre, Result};
+	use std::{future, time::Duration};
+	use test_case::test_case;
+	use tokio::time::sleep;
+
+	async fn delayed(millis: u64, result: Result<&'static str>) -> Result<&'static str> {
+		sleep(Duration::from_millis(millis)).await;
+		result
+	}
+
+	async fn never_polled() -> Result<&'static str> {
+		panic!("Fetch is not expected to be polled")
+	}
+
+	#[tokio::test]
+	async fn test_fetch_first_dht_first() {
+		let result = fetch_first(
+			CellFetchStrategy::DHTFirst,
+			delayed(0, Ok("dht")),
+			never_polled(),
+		);
+		assert_eq!(result.await.unwrap(), FirstFetch::DHT("dht"));
+	}
+
+	#[tokio::test]
+	async fn test_fetch_first_rpc_first() {
+		let result = fetch_first(
+			CellFetchStrategy::RPCFirst,
+			never_polled(),
+			delayed(0, Ok("rpc")),
+		);
+		assert_eq!(result.await.unwrap(), FirstFetch::RPC("rpc"));
+	}
+
+	#[tokio::test]
+	async fn test_fetch_first_propagates_error() {
+		let result = fetch_first(
+			CellFetchStrategy::DHTFirst,
+			delayed(0, Err(eyre!("dht"))),
+			never_polled(),
+		);
+		assert!(result.await.is_err());
+	}
+
+	#[test_case(10, Ok("dht"), 20, Ok("rpc"), Some(FirstFetch::DHT("dht")) ; "dht faster")]
+	#[test_case(20, Ok("dht"), 10, Ok("rpc"), Some(FirstFetch::RPC("rpc")) ; "rpc faster")]
+	#[test_case(10, Err(eyre!("dht")), 20, Ok("rpc"), Some(FirstFetch::RPC("rpc")) ; "dht fails")]
+	#[test_case(20, Ok("dht"), 10, Err(eyre!("rpc")), Some(FirstFetch::DHT("dht")) ; "rpc fails")]
+	#[test_case(10, Err(eyre!("dht")), 20, Err(eyre!("rpc")), None ; "both fail")]
+	#[tokio::test(start_paused = true)]
+	async fn test_fetch_first_parallel(
+		dht_delay: u64,
+		dht_result: Result<&'static str>,
+		rpc_delay: u64,
+		rpc_result: Result<&'static str>,
+		expected: Option<FirstFetch<&'static str>>,
+	) {
+		let result = fetch_first(
+			CellFetchStrategy::Parallel,
+			delayed(dht_delay, dht_result),
+			delayed(rpc_delay, rpc_result),
+		)
+		.await;
+		assert_eq!(result.ok(), expected);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_fetch_first_parallel_cancels_slower_fetch() {
+		let result = fetch_first(
+			CellFetchStrategy::Parallel,
+			delayed(10, Ok("dht")),
+			future::pending(),
+		);
+		assert_eq!(result.await.unwrap(), FirstFetch::DHT("dht"));
 	}
 }
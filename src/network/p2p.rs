@@ -18,18 +18,22 @@ use tracing::info;
 #[cfg(feature = "network-analysis")]
 pub mod analyzer;
 mod client;
+mod discovery_stats;
 mod event_loop;
 mod kad_mem_providers;
 mod kad_mem_store;
 mod kad_rocksdb_store;
+mod reputation;
 
 use crate::types::{LibP2PConfig, SecretKey};
 pub use client::Client;
+pub use discovery_stats::PeerDiscoveryStats;
 pub use event_loop::EventLoop;
 pub use kad_mem_providers::ProvidersConfig;
 pub use kad_mem_store::MemoryStoreConfig;
 pub use kad_rocksdb_store::ExpirationCompactionFilterFactory;
 pub use kad_rocksdb_store::RocksDBStoreConfig;
+pub use reputation::ReputationConfig;
 
 use self::{client::BlockStat, event_loop::ConnectionEstablishedInfo};
 use libp2p_allow_block_list as allow_block_list;
@@ -69,7 +69,7 @@ pub struct BlockStat {
 	pub remaining_counter: usize,
 	pub success_counter: usize,
 	pub error_counter: usize,
-	pub time_stat: u64,
+	pub time_stat: Duration,
 }
 
 impl BlockStat {
@@ -238,7 +238,7 @@ impl Command for PutKadRecord {
 				remaining_counter: self.records.len(),
 				success_counter: 0,
 				error_counter: 0,
-				time_stat: 0,
+				time_stat: Duration::ZERO,
 			});
 
 		for record in self.records.clone() {
@@ -0,0 +1,134 @@
+use std::{collections::VecDeque, time::Duration};
+use tokio::time::Instant;
+
+/// Timestamps of events which occurred within the sliding window.
+#[derive(Debug, Default)]
+struct WindowCounter {
+	events: VecDeque<Instant>,
+}
+
+impl WindowCounter {
+	fn record(&mut self, now: Instant, window: Duration) {
+		self.prune(now, window);
+		self.events.push_back(now);
+	}
+
+	fn prune(&mut self, now: Instant, window: Duration) {
+		while let Some(&oldest) = self.events.front() {
+			if now.duration_since(oldest) < window {
+				break;
+			}
+			self.events.pop_front();
+		}
+	}
+
+	fn count(&self, now: Instant, window: Duration) -> usize {
+		self.events
+			.iter()
+			.filter(|&&event| now.duration_since(event) < window)
+			.count()
+	}
+}
+
+/// Tracks rates of peer discoveries, established connections and closed connections.
+///
+/// Rates are number of events per second, calculated over the sliding window.
+#[derive(Debug)]
+pub struct PeerDiscoveryStats {
+	window: Duration,
+	discovered: WindowCounter,
+	connected: WindowCounter,
+	disconnected: WindowCounter,
+}
+
+impl Default for PeerDiscoveryStats {
+	fn default() -> Self {
+		Self::new(Duration::from_secs(60))
+	}
+}
+
+impl PeerDiscoveryStats {
+	pub fn new(window: Duration) -> Self {
+		Self {
+			window,
+			discovered: Default::default(),
+			connected: Default::default(),
+			disconnected: Default::default(),
+		}
+	}
+
+	/// Records that new peer is added to the routing table.
+	pub fn record_discovered(&mut self) {
+		self.discovered.record(Instant::now(), self.window);
+	}
+
+	/// Records established connection with the peer.
+	pub fn record_connected(&mut self) {
+		self.connected.record(Instant::now(), self.window);
+	}
+
+	/// Records closed connection with the peer.
+	pub fn record_disconnected(&mut self) {
+		self.disconnected.record(Instant::now(), self.window);
+	}
+
+	fn rate(&self, counter: &WindowCounter) -> f64 {
+		let count = counter.count(Instant::now(), self.window);
+		count as f64 / self.window.as_secs_f64()
+	}
+
+	/// Returns number of discovered peers per second.
+	pub fn discovery_rate(&self) -> f64 {
+		self.rate(&self.discovered)
+	}
+
+	/// Returns number of established connections per second.
+	pub fn connection_rate(&self) -> f64 {
+		self.rate(&self.connected)
+	}
+
+	/// Returns number of closed connections per second.
+	pub fn churn_rate(&self) -> f64 {
+		self.rate(&self.disconnected)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::PeerDiscoveryStats;
+	use std::time::Duration;
+	use tokio::time;
+
+	#[tokio::test(start_paused = true)]
+	async fn rates_within_window() {
+		let mut stats = PeerDiscoveryStats::new(Duration::from_secs(10));
+		assert_eq!(stats.discovery_rate(), 0.0);
+
+		for _ in 0..5 {
+			stats.record_discovered();
+		}
+		stats.record_connected();
+		stats.record_connected();
+		stats.record_disconnected();
+
+		assert_eq!(stats.discovery_rate(), 0.5);
+		assert_eq!(stats.connection_rate(), 0.2);
+		assert_eq!(stats.churn_rate(), 0.1);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn events_leave_window() {
+		let mut stats = PeerDiscoveryStats::new(Duration::from_secs(10));
+		stats.record_connected();
+
+		time::advance(Duration::from_secs(6)).await;
+		stats.record_connected();
+		assert_eq!(stats.connection_rate(), 0.2);
+
+		time::advance(Duration::from_secs(5)).await;
+		assert_eq!(stats.connection_rate(), 0.1);
+
+		time::advance(Duration::from_secs(5)).await;
+		assert_eq!(stats.connection_rate(), 0.0);
+	}
+}
@@ -29,15 +29,19 @@ use tracing::{debug, error, info, trace, warn};
 
 use crate::{
 	shutdown::Controller,
-	telemetry::{MetricCounter, MetricValue, Metrics},
+	telemetry::{MetricCounter, MetricValue, Metrics, MetricsExt},
 	types::{AgentVersion, IdentifyConfig, KademliaMode, LibP2PConfig, TimeToLive},
 };
 
 use super::{
-	build_swarm, client::BlockStat, Behaviour, BehaviourEvent, CommandReceiver, EventLoopEntries,
+	build_swarm, client::BlockStat, discovery_stats::PeerDiscoveryStats,
+	reputation::PeerReputationStore, Behaviour, BehaviourEvent, CommandReceiver, EventLoopEntries,
 	QueryChannel, SendableCommand,
 };
 
+/// Interval of the peer discovery rates recording, rates are calculated over `peer_stats_window`
+const DISCOVERY_STATS_INTERVAL: Duration = Duration::from_secs(10);
+
 // RelayState keeps track of all things relay related
 struct RelayState {
 	// id of the selected Relay that needs to be connected
@@ -102,6 +106,14 @@ pub struct EventLoop {
 	bootstrap: BootstrapState,
 	/// Blocks we monitor for PUT success rate
 	active_blocks: HashMap<u32, BlockStat>,
+	/// Connection success rates of remote peers
+	reputation: PeerReputationStore,
+	/// Timer of the peer reputation score recovery
+	reputation_timer: Interval,
+	/// Rates of peer discoveries and connection changes
+	discovery_stats: PeerDiscoveryStats,
+	/// Timer of the peer discovery rates recording
+	discovery_stats_timer: Interval,
 	shutdown: Controller<String>,
 	event_loop_config: EventLoopConfig,
 }
@@ -171,6 +183,16 @@ impl EventLoop {
 				timer: interval_at(Instant::now() + bootstrap_interval, bootstrap_interval),
 			},
 			active_blocks: Default::default(),
+			reputation: PeerReputationStore::new((&cfg).into()),
+			reputation_timer: interval_at(
+				Instant::now() + cfg.peer_score_recovery_interval,
+				cfg.peer_score_recovery_interval,
+			),
+			discovery_stats: PeerDiscoveryStats::new(cfg.peer_stats_window),
+			discovery_stats_timer: interval_at(
+				Instant::now() + DISCOVERY_STATS_INTERVAL,
+				DISCOVERY_STATS_INTERVAL,
+			),
 			shutdown,
 			event_loop_config: EventLoopConfig {
 				identity_data: cfg.identify,
@@ -199,6 +221,8 @@ impl EventLoop {
 					},
 				},
 				_ = self.bootstrap.timer.tick() => self.handle_periodic_bootstraps(),
+				_ = self.reputation_timer.tick() => self.handle_reputation_recovery(),
+				_ = self.discovery_stats_timer.tick() => self.record_discovery_stats(&metrics).await,
 				// if the shutdown was triggered,
 				// break the loop immediately, proceed to the cleanup phase
 				_ = self.shutdown.triggered_shutdown() => {
@@ -235,6 +259,9 @@ impl EventLoop {
 						..
 					} => {
 						trace!("Routing updated. Peer: {peer:?}. is_new_peer: {is_new_peer:?}. Addresses: {addresses:#?}. Old peer: {old_peer:#?}");
+						if is_new_peer {
+							self.discovery_stats.record_discovered();
+						}
 					},
 					kad::Event::RoutablePeer { peer, address } => {
 						trace!("RoutablePeer. Peer: {peer:?}.  Address: {address:?}");
@@ -247,10 +274,14 @@ impl EventLoop {
 					},
 					kad::Event::InboundRequest { request } => match request {
 						InboundRequest::GetRecord { .. } => {
-							metrics.count(MetricCounter::IncomingGetRecord).await;
+							metrics
+								.count_or_warn(MetricCounter::IncomingGetRecord)
+								.await;
 						},
 						InboundRequest::PutRecord { source, record, .. } => {
-							metrics.count(MetricCounter::IncomingPutRecord).await;
+							metrics
+								.count_or_warn(MetricCounter::IncomingPutRecord)
+								.await;
 							match record {
 								Some(mut record) => {
 									let ttl = &self.event_loop_config.kad_record_ttl;
@@ -469,8 +500,8 @@ impl EventLoop {
 			},
 			SwarmEvent::Behaviour(BehaviourEvent::Ping(ping::Event { result, .. })) => {
 				if let Ok(rtt) = result {
-					let _ = metrics
-						.record(MetricValue::DHTPingLatency(rtt.as_millis() as f64))
+					metrics
+						.record_or_warn(MetricValue::DHTPingLatency(rtt.as_millis() as f64))
 						.await;
 				}
 			},
@@ -501,6 +532,7 @@ impl EventLoop {
 						..
 					} => {
 						trace!("Connection closed. PeerID: {peer_id:?}. Address: {:?}. Num established: {num_established:?}. Cause: {cause:?}", endpoint.get_remote_address());
+						self.discovery_stats.record_disconnected();
 
 						if let Some(ConnectionError::IO(_)) = cause {
 							// remove peer with failed connection
@@ -508,10 +540,14 @@ impl EventLoop {
 						}
 					},
 					SwarmEvent::IncomingConnection { .. } => {
-						metrics.count(MetricCounter::IncomingConnections).await;
+						metrics
+							.count_or_warn(MetricCounter::IncomingConnections)
+							.await;
 					},
 					SwarmEvent::IncomingConnectionError { .. } => {
-						metrics.count(MetricCounter::IncomingConnectionErrors).await;
+						metrics
+							.count_or_warn(MetricCounter::IncomingConnectionErrors)
+							.await;
 					},
 					SwarmEvent::ExternalAddrConfirmed { address } => {
 						info!(
@@ -526,7 +562,11 @@ impl EventLoop {
 						num_established,
 						..
 					} => {
-						metrics.count(MetricCounter::EstablishedConnections).await;
+						metrics
+							.count_or_warn(MetricCounter::EstablishedConnections)
+							.await;
+						self.reputation.record_success(&peer_id);
+						self.discovery_stats.record_connected();
 						endpoint.get_remote_address();
 						// Notify the connections we're waiting on that we've connected successfully
 						if let Some(ch) = self.pending_swarm_events.remove(&peer_id) {
@@ -540,9 +580,21 @@ impl EventLoop {
 						self.establish_relay_circuit(peer_id);
 					},
 					SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
-						metrics.count(MetricCounter::OutgoingConnectionErrors).await;
+						metrics
+							.count_or_warn(MetricCounter::OutgoingConnectionErrors)
+							.await;
 
 						if let Some(peer_id) = peer_id {
+							self.reputation.record_failure(&peer_id);
+							if self.reputation.is_banned(&peer_id) {
+								debug!(
+									"Blocking peer {peer_id} with reputation score {}",
+									self.reputation.score(&peer_id)
+								);
+								self.swarm.behaviour_mut().kademlia.remove_peer(&peer_id);
+								self.swarm.behaviour_mut().blocked_peers.block_peer(peer_id);
+							}
+
 							// Notify the connections we're waiting on an error has occurred
 							if let libp2p::swarm::DialError::WrongPeerId { .. } = &error {
 								if let Some(peer) =
@@ -576,6 +628,19 @@ impl EventLoop {
 		}
 	}
 
+	async fn record_discovery_stats(&self, metrics: &Arc<impl Metrics>) {
+		let stats = &self.discovery_stats;
+		metrics
+			.record_or_warn(MetricValue::PeerDiscoveryRate(stats.discovery_rate()))
+			.await;
+		metrics
+			.record_or_warn(MetricValue::PeerConnectionRate(stats.connection_rate()))
+			.await;
+		metrics
+			.record_or_warn(MetricValue::PeerChurnRate(stats.churn_rate()))
+			.await;
+	}
+
 	async fn handle_command(&mut self, mut command: SendableCommand) {
 		if let Err(err) = command.run(EventLoopEntries::new(
 			&mut self.swarm,
@@ -595,6 +660,17 @@ impl EventLoop {
 		}
 	}
 
+	fn handle_reputation_recovery(&mut self) {
+		// peers blocked because of the low reputation score are given another chance
+		for peer_id in self.reputation.recover() {
+			debug!("Unblocking peer {peer_id} with recovered reputation score");
+			self.swarm
+				.behaviour_mut()
+				.blocked_peers
+				.unblock_peer(peer_id);
+		}
+	}
+
 	fn establish_relay_circuit(&mut self, peer_id: PeerId) {
 		// before we try and create a circuit with the relay
 		// we have to exchange observed addresses
@@ -668,24 +744,20 @@ impl EventLoop {
 				block.success_counter += 1;
 			}
 
-			block.time_stat = stats
-				.duration()
-				.as_ref()
-				.map(Duration::as_secs)
-				.unwrap_or_default();
+			block.time_stat = stats.duration().unwrap_or_default();
 
 			if block.remaining_counter == 0 {
 				let success_rate = block.success_counter as f64 / block.total_count as f64;
 				info!(
-					"Cell upload success rate for block {block_num}: {}/{}. Duration: {}",
+					"Cell upload success rate for block {block_num}: {}/{}. Duration: {:?}",
 					block.success_counter, block.total_count, block.time_stat
 				);
-				_ = metrics
-					.record(MetricValue::DHTPutSuccess(success_rate))
+				metrics
+					.record_or_warn(MetricValue::DHTPutSuccess(success_rate))
 					.await;
 
-				_ = metrics
-					.record(MetricValue::DHTPutDuration(block.time_stat as f64))
+				metrics
+					.record_or_warn(MetricValue::DHTPutDuration(block.time_stat))
 					.await;
 			}
 
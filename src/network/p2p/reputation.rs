@@ -0,0 +1,183 @@
+use libp2p::PeerId;
+use std::collections::{HashMap, HashSet};
+
+/// Configuration for a `PeerReputationStore`.
+#[derive(Debug, Clone)]
+pub struct ReputationConfig {
+	/// Peers with score below this threshold are considered banned.
+	pub ban_threshold: f64,
+	/// Weight of the previous score when new outcome is recorded, in range [0, 1).
+	/// Higher values make score change slower.
+	pub decay: f64,
+	/// Peers which are never banned (e.g. bootstrap peers)
+	pub exempt_peers: HashSet<PeerId>,
+}
+
+impl Default for ReputationConfig {
+	fn default() -> Self {
+		Self {
+			ban_threshold: 0.2,
+			decay: 0.9,
+			exempt_peers: Default::default(),
+		}
+	}
+}
+
+/// Tracks connection success and failure rates of remote peers.
+///
+/// Score of each peer is exponentially weighted moving average of connection outcomes,
+/// where success counts as 1 and failure as 0. Unknown peers have maximum score of 1.
+/// Scores recover over time (see [`PeerReputationStore::recover`]), so bans are not permanent.
+#[derive(Debug, Default)]
+pub struct PeerReputationStore {
+	config: ReputationConfig,
+	scores: HashMap<PeerId, f64>,
+}
+
+impl PeerReputationStore {
+	const MAX_SCORE: f64 = 1.0;
+	/// Scores closer than this to the maximum score are considered neutral and pruned
+	const NEUTRAL_TOLERANCE: f64 = 0.01;
+
+	pub fn new(config: ReputationConfig) -> Self {
+		Self {
+			config,
+			scores: Default::default(),
+		}
+	}
+
+	fn update(decay: f64, score: &mut f64, outcome: f64) {
+		*score = decay * *score + (1.0 - decay) * outcome;
+	}
+
+	fn is_neutral(score: f64) -> bool {
+		Self::MAX_SCORE - score < Self::NEUTRAL_TOLERANCE
+	}
+
+	fn record(&mut self, peer: &PeerId, outcome: f64) {
+		if self.config.exempt_peers.contains(peer) {
+			return;
+		}
+		let score = self.scores.entry(*peer).or_insert(Self::MAX_SCORE);
+		Self::update(self.config.decay, score, outcome);
+		if Self::is_neutral(*score) {
+			self.scores.remove(peer);
+		}
+	}
+
+	/// Records successful connection with the peer.
+	pub fn record_success(&mut self, peer: &PeerId) {
+		self.record(peer, 1.0);
+	}
+
+	/// Records failed connection attempt with the peer.
+	pub fn record_failure(&mut self, peer: &PeerId) {
+		self.record(peer, 0.0);
+	}
+
+	/// Returns peer score in range [0, 1].
+	pub fn score(&self, peer: &PeerId) -> f64 {
+		self.scores.get(peer).copied().unwrap_or(Self::MAX_SCORE)
+	}
+
+	/// Checks if peer score dropped below the ban threshold.
+	pub fn is_banned(&self, peer: &PeerId) -> bool {
+		self.score(peer) < self.config.ban_threshold
+	}
+
+	/// Recovers scores of all peers, as if a successful connection was recorded.
+	/// Scores which recovered to the neutral value are pruned.
+	/// Returns peers which were banned before the recovery, and are not banned anymore.
+	pub fn recover(&mut self) -> Vec<PeerId> {
+		let ReputationConfig {
+			ban_threshold,
+			decay,
+			..
+		} = self.config;
+		let mut unbanned = vec![];
+		self.scores.retain(|peer, score| {
+			let was_banned = *score < ban_threshold;
+			Self::update(decay, score, Self::MAX_SCORE);
+			if was_banned && *score >= ban_threshold {
+				unbanned.push(*peer);
+			}
+			!Self::is_neutral(*score)
+		});
+		unbanned
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{PeerReputationStore, ReputationConfig};
+	use libp2p::PeerId;
+
+	#[test]
+	fn unknown_peer_has_max_score() {
+		let store = PeerReputationStore::default();
+		let peer = PeerId::random();
+		assert_eq!(store.score(&peer), 1.0);
+		assert!(!store.is_banned(&peer));
+	}
+
+	#[test]
+	fn repeated_failures_ban_peer() {
+		let mut store = PeerReputationStore::new(ReputationConfig {
+			ban_threshold: 0.5,
+			decay: 0.5,
+			..Default::default()
+		});
+		let peer = PeerId::random();
+		let other = PeerId::random();
+
+		store.record_failure(&peer);
+		assert_eq!(store.score(&peer), 0.5);
+		assert!(!store.is_banned(&peer));
+
+		store.record_failure(&peer);
+		assert_eq!(store.score(&peer), 0.25);
+		assert!(store.is_banned(&peer));
+		assert!(!store.is_banned(&other));
+
+		store.record_success(&peer);
+		assert_eq!(store.score(&peer), 0.625);
+		assert!(!store.is_banned(&peer));
+	}
+
+	#[test]
+	fn exempt_peer_is_never_banned() {
+		let peer = PeerId::random();
+		let mut store = PeerReputationStore::new(ReputationConfig {
+			exempt_peers: [peer].into(),
+			..Default::default()
+		});
+		for _ in 0..100 {
+			store.record_failure(&peer);
+		}
+		assert!(!store.is_banned(&peer));
+		assert!(store.scores.is_empty());
+	}
+
+	#[test]
+	fn banned_peer_recovers() {
+		let mut store = PeerReputationStore::new(ReputationConfig {
+			ban_threshold: 0.5,
+			decay: 0.5,
+			..Default::default()
+		});
+		let peer = PeerId::random();
+		store.record_failure(&peer);
+		store.record_failure(&peer);
+		assert!(store.is_banned(&peer));
+
+		assert_eq!(store.recover(), vec![peer]);
+		assert_eq!(store.score(&peer), 0.625);
+		assert!(!store.is_banned(&peer));
+
+		// Score is pruned once it recovers to the neutral value
+		while !store.scores.is_empty() {
+			assert!(store.recover().is_empty());
+		}
+		assert_eq!(store.score(&peer), 1.0);
+	}
+}
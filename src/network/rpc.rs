@@ -1,13 +1,22 @@
+use avail_core::DataLookup;
 use avail_subxt::{primitives::Header, utils::H256};
 use codec::Decode;
-use color_eyre::{eyre::eyre, Result};
-use kate_recovery::matrix::{Dimensions, Position};
-use rand::{seq::SliceRandom, thread_rng, Rng};
+use color_eyre::{eyre::WrapErr, Result};
+use futures::future;
+use kate_recovery::{
+	com::app_specific_rows,
+	config::COMMITMENT_SIZE,
+	data::Cell,
+	matrix::{Dimensions, Position},
+};
+use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
 use serde::{de, Deserialize};
 use sp_core::bytes::from_hex;
 use std::{
 	collections::HashSet,
 	fmt::Display,
+	future::Future,
+	num::NonZeroUsize,
 	sync::{Arc, Mutex},
 };
 use tokio::{
@@ -23,6 +32,8 @@ use crate::{
 	types::{GrandpaJustification, RetryConfig, State},
 };
 
+mod cache;
+mod circuit_breaker;
 mod client;
 mod subscriptions;
 
@@ -32,7 +43,48 @@ const PROOF_SIZE: usize = 48;
 pub const CELL_WITH_PROOF_SIZE: usize = CELL_SIZE + PROOF_SIZE;
 pub use subscriptions::Event;
 
-pub use client::Client;
+pub use cache::HeaderCache;
+pub use circuit_breaker::{CircuitBreakerConfig, CircuitBreakers, CircuitState};
+pub use client::{
+	get_runtime_version, get_system_version, health_check_nodes, reachable_nodes, Client,
+	ConnectionResult, NodeHealthReport,
+};
+
+/// Errors returned by the public RPC functions and [`Client`] methods.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RpcError {
+	/// Block with the given number is not found
+	BlockNotFound(u32),
+	/// Header of the block with the given hash is not found
+	HeaderNotFound(H256),
+	/// Fetching of the cell proofs failed
+	ProofFetchFailed(String),
+	/// None of the configured nodes is reachable
+	ConnectionFailed(String),
+	/// Node system version is not supported
+	VersionMismatch { expected: String, found: String },
+	/// Request failed for other reasons, e.g. malformed response or shutdown
+	RequestFailed(String),
+}
+
+impl Display for RpcError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			RpcError::BlockNotFound(number) => write!(f, "Block with number: {number} not found"),
+			RpcError::HeaderNotFound(hash) => {
+				write!(f, "Block Header with hash: {hash:?} not found")
+			},
+			RpcError::ProofFetchFailed(error) => write!(f, "Failed to fetch proofs: {error}"),
+			RpcError::ConnectionFailed(error) => write!(f, "Connection failed: {error}"),
+			RpcError::VersionMismatch { expected, found } => {
+				write!(f, "Expected Node system version:{expected}, found: {found}")
+			},
+			RpcError::RequestFailed(error) => write!(f, "Request failed: {error}"),
+		}
+	}
+}
+
+impl std::error::Error for RpcError {}
 
 pub enum Subscription {
 	Header(Header),
@@ -44,8 +96,10 @@ pub struct WrappedJustification(pub GrandpaJustification);
 
 impl Decode for WrappedJustification {
 	fn decode<I: codec::Input>(input: &mut I) -> std::result::Result<Self, codec::Error> {
-		let j: Vec<u8> = Decode::decode(input)?;
-		let jj: GrandpaJustification = Decode::decode(&mut &j[..])?;
+		let j: Vec<u8> = Decode::decode(input)
+			.map_err(|error| error.chain("Failed to decode justification bytes"))?;
+		let jj: GrandpaJustification = Decode::decode(&mut &j[..])
+			.map_err(|error| error.chain("Failed to decode Grandpa justification"))?;
 		Ok(WrappedJustification(jj))
 	}
 }
@@ -69,8 +123,9 @@ impl<'de> Deserialize<'de> for WrappedProof {
 		D: serde::Deserializer<'de>,
 	{
 		let data = from_hex(&String::deserialize(deserializer)?)
-			.map_err(|e| de::Error::custom(format!("{:?}", e)))?;
-		Decode::decode(&mut &data[..]).map_err(|e| de::Error::custom(format!("{:?}", e)))
+			.map_err(|e| de::Error::custom(format!("Invalid finality proof hex: {:?}", e)))?;
+		Decode::decode(&mut &data[..])
+			.map_err(|e| de::Error::custom(format!("Failed to decode finality proof: {:?}", e)))
 	}
 }
 
@@ -130,8 +185,14 @@ pub struct Nodes {
 }
 
 impl Nodes {
+	/// Creates list of Nodes from given hosts.
+	/// Duplicated hosts are removed, preserving the order of first occurrences.
 	pub fn new(nodes: &[String]) -> Self {
-		let candidates = nodes.to_owned();
+		let mut unique_hosts = HashSet::new();
+		let candidates = nodes
+			.iter()
+			.filter(|&host| unique_hosts.insert(host))
+			.collect::<Vec<_>>();
 		Self {
 			list: candidates
 				.iter()
@@ -189,48 +250,184 @@ impl<'a> Iterator for NodesIterator<'a> {
 	}
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn init<T: Database>(
 	db: T,
 	state: Arc<Mutex<State>>,
 	nodes: &[String],
 	genesis_hash: &str,
 	retry_config: RetryConfig,
+	max_backoff: time::Duration,
+	header_cache_capacity: NonZeroUsize,
+	circuit_breaker_config: CircuitBreakerConfig,
 	shutdown: Controller<String>,
-) -> Result<(Client, broadcast::Sender<Event>, SubscriptionLoop<T>)> {
+) -> Result<(Client, broadcast::Sender<Event>, SubscriptionLoop<T>), RpcError> {
+	let nodes = Nodes::new(nodes);
+	let circuit_breakers = CircuitBreakers::new(circuit_breaker_config, nodes.iter().count());
 	let rpc_client = Client::new(
 		state.clone(),
-		Nodes::new(nodes),
+		nodes,
 		genesis_hash,
 		retry_config,
+		max_backoff,
+		HeaderCache::new(header_cache_capacity),
+		circuit_breakers,
 		shutdown,
 	)
-	.await?;
+	.await
+	.wrap_err("init: failed to create RPC client")?;
 	// create output channel for RPC Subscription Events
 	let (event_sender, _) = broadcast::channel(1000);
-	let subscriptions =
-		SubscriptionLoop::new(state, db, rpc_client.clone(), event_sender.clone()).await?;
+	let subscriptions = SubscriptionLoop::new(state, db, rpc_client.clone(), event_sender.clone())
+		.await
+		.wrap_err("init: failed to create RPC subscription loop")?;
 
 	Ok((rpc_client, event_sender, subscriptions))
 }
 
+/// Quantities derived from the block matrix dimensions
+pub trait DimensionsExt {
+	/// Number of cells in the matrix, before extension
+	fn total_cells(&self) -> u64;
+	/// Number of cells in a single row
+	fn cells_per_row(&self) -> u16;
+	/// Size of the commitments of the original matrix rows, in bytes
+	fn commitment_size_bytes(&self) -> usize;
+}
+
+impl DimensionsExt for Dimensions {
+	fn total_cells(&self) -> u64 {
+		u64::from(self.rows().get()) * u64::from(self.cols().get())
+	}
+
+	fn cells_per_row(&self) -> u16 {
+		self.cols().get()
+	}
+
+	fn commitment_size_bytes(&self) -> usize {
+		usize::from(self.rows().get()) * COMMITMENT_SIZE
+	}
+}
+
 /// Generates random cell positions for sampling
 pub fn generate_random_cells(dimensions: Dimensions, cell_count: u32) -> Vec<Position> {
-	let max_cells = dimensions.extended_size();
+	let rows = (0..dimensions.extended_rows()).collect::<Vec<_>>();
+	random_positions(
+		&rows,
+		dimensions.cells_per_row(),
+		cell_count,
+		&mut thread_rng(),
+	)
+}
+
+/// Generates cell positions for sampling, deterministically derived from the given seed.
+/// Seed derived from the block hash makes sampling reproducible, but unpredictable before the block is produced.
+pub fn generate_random_cells_seeded(
+	dimensions: Dimensions,
+	cell_count: u32,
+	seed: u64,
+) -> Vec<Position> {
+	let rows = (0..dimensions.extended_rows()).collect::<Vec<_>>();
+	let mut rng = StdRng::seed_from_u64(seed);
+	random_positions(&rows, dimensions.cells_per_row(), cell_count, &mut rng)
+}
+
+/// Generates random cell positions, sampling only rows which belong to the given application.
+/// Returns empty list if application has no rows in the block.
+pub fn generate_cells_for_app(
+	dimensions: &Dimensions,
+	lookup: &DataLookup,
+	app_id: u32,
+	cell_count: u32,
+) -> Vec<Position> {
+	let rows = app_specific_rows(lookup, *dimensions, app_id);
+	random_positions(
+		&rows,
+		dimensions.cells_per_row(),
+		cell_count,
+		&mut thread_rng(),
+	)
+}
+
+/// Samples up to `cell_count` unique positions from the given rows and `0..cols` columns.
+/// Positions are returned in the sampling order, so the same random generator state gives the same result.
+fn random_positions(rows: &[u32], cols: u16, cell_count: u32, rng: &mut impl Rng) -> Vec<Position> {
+	let max_cells = rows.len() as u32 * cols as u32;
 	let count = if max_cells < cell_count {
 		debug!("Max cells count {max_cells} is lesser than cell_count {cell_count}");
 		max_cells
 	} else {
 		cell_count
 	};
-	let mut rng = thread_rng();
 	let mut indices = HashSet::new();
-	while (indices.len() as u16) < count as u16 {
-		let col = rng.gen_range(0..dimensions.cols().into());
-		let row = rng.gen_range(0..dimensions.extended_rows());
-		indices.insert(Position { row, col });
+	let mut positions = Vec::with_capacity(count as usize);
+	while (positions.len() as u32) < count {
+		let Some(&row) = rows.choose(rng) else {
+			break;
+		};
+		let col = rng.gen_range(0..cols);
+		if indices.insert((row, col)) {
+			positions.push(Position { row, col });
+		}
 	}
 
-	indices.into_iter().collect::<Vec<_>>()
+	positions
+}
+
+/// Merges position sets into a single list without duplicates, preserving order of first occurrences
+pub fn deduplicate_positions(sets: &[Vec<Position>]) -> Vec<Position> {
+	let mut unique_positions = HashSet::new();
+	sets.iter()
+		.flatten()
+		.filter(|&position| unique_positions.insert(position))
+		.cloned()
+		.collect()
+}
+
+/// Fetches proofs for union of the given position sets, so each unique position is requested only once.
+/// Overlapping sets are produced when positions of the same block are generated multiple times.
+pub async fn fetch_cells_deduplicated<F, Fut>(
+	position_sets: &[Vec<Position>],
+	get_kate_proof: F,
+) -> Result<Vec<Cell>, RpcError>
+where
+	F: FnOnce(Vec<Position>) -> Fut,
+	Fut: Future<Output = Result<Vec<Cell>, RpcError>>,
+{
+	get_kate_proof(deduplicate_positions(position_sets)).await
+}
+
+/// Fetches proofs of the given positions in chunks of at most `max_cells` positions,
+/// since nodes can limit the number of cells per proof query.
+/// Up to `parallel_tasks` chunks are requested concurrently, so with 1 requests are sequential.
+/// Cells are returned in the order of the given positions.
+/// Proof fetch failures are extended with the index of the failed chunk.
+pub async fn get_kate_proof_chunked<'a, F, Fut>(
+	positions: &'a [Position],
+	max_cells: usize,
+	parallel_tasks: usize,
+	get_kate_proof: F,
+) -> Result<Vec<Cell>, RpcError>
+where
+	F: Fn(&'a [Position]) -> Fut,
+	Fut: Future<Output = Result<Vec<Cell>, RpcError>>,
+{
+	let chunks = positions.chunks(max_cells.max(1)).collect::<Vec<_>>();
+	let mut cells = Vec::with_capacity(positions.len());
+	for (batch, parallel_chunks) in chunks.chunks(parallel_tasks.max(1)).enumerate() {
+		let requests = parallel_chunks.iter().map(|&chunk| get_kate_proof(chunk));
+		for (i, result) in future::join_all(requests).await.into_iter().enumerate() {
+			let chunk = batch * parallel_tasks.max(1) + i;
+			cells.extend(result.map_err(|error| match error {
+				RpcError::ProofFetchFailed(error) => RpcError::ProofFetchFailed(format!(
+					"get_kate_proof_chunked: chunk {chunk} of {} positions: {error}",
+					positions.len()
+				)),
+				error => error,
+			})?);
+		}
+	}
+	Ok(cells)
 }
 
 /* @note: fn to take the number of cells needs to get equal to or greater than
@@ -238,6 +435,25 @@ the percentage of confidence mentioned in config file */
 
 pub const CELL_COUNT_99_99: u32 = 14;
 
+/// Maximum confidence which can be achieved by sampling [`CELL_COUNT_99_99`] cells
+pub const MAX_CONFIDENCE: f64 = 99.99;
+
+/// Model used to calculate number of cells needed for sampling
+pub trait ConfidenceCalculator: std::fmt::Debug {
+	/// Returns number of cells required to achieve given confidence
+	fn cells_needed(&self, confidence: f64) -> u32;
+}
+
+/// Confidence model which assumes that cells are sampled independently (see [`cell_count_for_confidence`])
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultConfidenceCalculator;
+
+impl ConfidenceCalculator for DefaultConfidenceCalculator {
+	fn cells_needed(&self, confidence: f64) -> u32 {
+		cell_count_for_confidence(confidence)
+	}
+}
+
 /// Calculates number of cells required to achieve given confidence
 pub fn cell_count_for_confidence(confidence: f64) -> u32 {
 	let mut cell_count: u32;
@@ -267,14 +483,255 @@ pub fn cell_count_for_confidence(confidence: f64) -> u32 {
 	cell_count
 }
 
+/// Returns confidence which is actually used for sampling by [`cell_count_for_confidence`].
+/// Values outside of range [50, 100] are replaced with 99, and values above 99.99 are capped,
+/// since the number of sampled cells is limited to [`CELL_COUNT_99_99`].
+pub fn effective_confidence(confidence: f64) -> f64 {
+	if !(50.0..=100f64).contains(&confidence) {
+		return 99.0;
+	}
+	confidence.min(MAX_CONFIDENCE)
+}
+
+/// Calculates confidence achieved with given number of cells, inverse of [`cell_count_for_confidence`].
+/// Confidence is capped at [`MAX_CONFIDENCE`], so it never reaches 100 for large cell counts.
+pub fn confidence_for_cell_count(cell_count: u32) -> f64 {
+	if cell_count == 0 {
+		return 0.0;
+	}
+	let confidence = 100f64 * (1f64 - 0.5f64.powf(cell_count as f64));
+	confidence.min(MAX_CONFIDENCE)
+}
+
 pub async fn wait_for_finalized_header(
 	mut rpc_events_receiver: broadcast::Receiver<Event>,
 	timeout_seconds: u64,
-) -> Result<Header> {
+) -> Result<Header, RpcError> {
 	let timeout_seconds = time::Duration::from_secs(timeout_seconds);
 	match timeout(timeout_seconds, rpc_events_receiver.recv()).await {
 		Ok(Ok(rpc::Event::HeaderUpdate { header, .. })) => Ok(header),
-		Ok(Err(error)) => Err(eyre!("Failed to receive finalized header: {error}")),
-		Err(_) => Err(eyre!("Timeout on waiting for first finalized header")),
+		Ok(Err(error)) => Err(RpcError::RequestFailed(format!(
+			"Failed to receive finalized header: {error}"
+		))),
+		Err(_) => Err(RpcError::RequestFailed(
+			"Timeout on waiting for first finalized header".to_string(),
+		)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::consts::{MAX_BLOCK_COLUMNS, MAX_BLOCK_ROWS};
+	use color_eyre::Report;
+	use proptest::{collection::vec, prelude::any, proptest, sample::select};
+	use test_case::test_case;
+
+	#[test_case(40.0 => 99.0 ; "below range")]
+	#[test_case(120.0 => 99.0 ; "above range")]
+	#[test_case(99.9 => 99.9 ; "in range")]
+	#[test_case(99.999 => 99.99 ; "capped")]
+	fn test_effective_confidence(confidence: f64) -> f64 {
+		effective_confidence(confidence)
+	}
+
+	#[test_case(1 ; "sequential")]
+	#[test_case(2 ; "parallel")]
+	#[tokio::test]
+	async fn test_get_kate_proof_chunked(parallel_tasks: usize) {
+		let positions = (0..7)
+			.map(|col| Position { row: 0, col })
+			.collect::<Vec<_>>();
+		let calls = Mutex::new(vec![]);
+		let get_kate_proof = |chunk: &[Position]| {
+			calls.lock().unwrap().push(chunk.len());
+			let cells = chunk
+				.iter()
+				.map(|&position| Cell {
+					position,
+					content: [0; CELL_WITH_PROOF_SIZE],
+				})
+				.collect::<Vec<_>>();
+			async move { Ok(cells) }
+		};
+
+		let cells = get_kate_proof_chunked(&positions, 3, parallel_tasks, get_kate_proof)
+			.await
+			.unwrap();
+		assert_eq!(*calls.lock().unwrap(), vec![3, 3, 1]);
+		let fetched = cells.iter().map(|cell| cell.position).collect::<Vec<_>>();
+		assert_eq!(fetched, positions);
+	}
+
+	#[tokio::test]
+	async fn test_fetch_cells_deduplicated() {
+		let position = |row, col| Position { row, col };
+		let first = vec![position(0, 0), position(0, 1), position(1, 1)];
+		let second = vec![position(1, 1), position(2, 0), position(0, 0)];
+		let requested = Mutex::new(vec![]);
+		let get_kate_proof = |positions: Vec<Position>| {
+			requested.lock().unwrap().extend(positions.iter().copied());
+			let cells = positions
+				.into_iter()
+				.map(|position| Cell {
+					position,
+					content: [0; CELL_WITH_PROOF_SIZE],
+				})
+				.collect::<Vec<_>>();
+			async move { Ok(cells) }
+		};
+
+		let cells = fetch_cells_deduplicated(&[first.clone(), second.clone()], get_kate_proof)
+			.await
+			.unwrap();
+		let requested = requested.into_inner().unwrap();
+		// Each unique position is requested exactly once
+		for position in first.iter().chain(&second) {
+			let calls = requested.iter().filter(|&p| p == position).count();
+			assert_eq!(calls, 1);
+		}
+		assert_eq!(requested.len(), 4);
+		assert_eq!(cells.len(), 4);
+	}
+
+	#[test]
+	fn test_rpc_error_downcast() {
+		let report: Report = RpcError::BlockNotFound(42).into();
+		assert_eq!(report.to_string(), "Block with number: 42 not found");
+		assert_eq!(
+			report.downcast_ref::<RpcError>(),
+			Some(&RpcError::BlockNotFound(42))
+		);
+
+		let report = Report::from(RpcError::HeaderNotFound(H256::zero())).wrap_err("Context");
+		assert!(matches!(
+			report.downcast_ref::<RpcError>(),
+			Some(RpcError::HeaderNotFound(_))
+		));
+	}
+
+	#[test_case(1, 1 => (1, 1, 48) ; "smallest dimensions")]
+	#[test_case(1, 4 => (4, 4, 48) ; "single row")]
+	#[test_case(MAX_BLOCK_ROWS, MAX_BLOCK_COLUMNS => (262144, 256, 49152) ; "maximum dimensions")]
+	fn test_dimensions_ext(rows: u16, cols: u16) -> (u64, u16, usize) {
+		let dimensions = Dimensions::new(rows, cols).unwrap();
+		(
+			dimensions.total_cells(),
+			dimensions.cells_per_row(),
+			dimensions.commitment_size_bytes(),
+		)
+	}
+
+	#[test]
+	fn test_dimensions_without_rows() {
+		// Dimensions without rows cannot be constructed, so there is nothing to derive
+		assert!(Dimensions::new(0, 4).is_none());
+	}
+
+	#[tokio::test]
+	async fn test_get_kate_proof_chunked_error() {
+		let positions = (0..7)
+			.map(|col| Position { row: 0, col })
+			.collect::<Vec<_>>();
+		let get_kate_proof = |chunk: &[Position]| {
+			let failed = chunk[0].col == 3;
+			async move {
+				if failed {
+					Err(RpcError::ProofFetchFailed("Node error".to_string()))
+				} else {
+					Ok(vec![])
+				}
+			}
+		};
+
+		let error = get_kate_proof_chunked(&positions, 3, 2, get_kate_proof)
+			.await
+			.unwrap_err();
+		assert_eq!(
+			error,
+			RpcError::ProofFetchFailed(
+				"get_kate_proof_chunked: chunk 1 of 7 positions: Node error".to_string()
+			)
+		);
+	}
+
+	#[test_case("\"0xzz\"" => "Invalid finality proof hex" ; "invalid hex")]
+	#[test_case("\"0x0102\"" => "Failed to decode finality proof" ; "invalid proof")]
+	fn test_wrapped_proof_error(json: &str) -> String {
+		let error = serde_json::from_str::<WrappedProof>(json).unwrap_err();
+		error.to_string().split(':').next().unwrap().to_string()
+	}
+
+	#[test]
+	fn test_generate_random_cells_seeded() {
+		let dimensions = Dimensions::new(256, 256).unwrap();
+		let positions = generate_random_cells_seeded(dimensions, 10, 42);
+		assert_eq!(positions.len(), 10);
+		assert_eq!(positions, generate_random_cells_seeded(dimensions, 10, 42));
+		assert_ne!(positions, generate_random_cells_seeded(dimensions, 10, 43));
+	}
+
+	#[test]
+	fn test_generate_cells_for_app() {
+		let dimensions = Dimensions::new(2, 4).unwrap();
+		let id_lens: Vec<(u32, usize)> = vec![(0, 3), (1, 3)];
+		let lookup = DataLookup::from_id_and_len_iter(id_lens.into_iter()).unwrap();
+
+		assert!(generate_cells_for_app(&dimensions, &lookup, 2, 10).is_empty());
+
+		// App 1 spans both original rows, which are rows 0 and 2 of the extended matrix
+		let positions = generate_cells_for_app(&dimensions, &lookup, 1, 3);
+		assert_eq!(positions.len(), 3);
+		assert!(positions
+			.iter()
+			.all(|position| [0, 2].contains(&position.row)));
+
+		// Cell count is capped to the number of cells in the app rows
+		let positions = generate_cells_for_app(&dimensions, &lookup, 1, 100);
+		assert_eq!(positions.len(), 8);
+		assert_eq!(deduplicate_positions(&[positions.clone()]), positions);
+	}
+
+	#[test]
+	fn test_deduplicate_positions() {
+		let position = |row, col| Position { row, col };
+		assert!(deduplicate_positions(&[]).is_empty());
+
+		let first = vec![position(0, 0), position(0, 1), position(1, 1)];
+		let second = vec![position(1, 1), position(2, 0), position(0, 0)];
+		assert_eq!(
+			deduplicate_positions(&[first, second]),
+			vec![
+				position(0, 0),
+				position(0, 1),
+				position(1, 1),
+				position(2, 0)
+			]
+		);
+	}
+
+	proptest! {
+	#[test]
+	fn nodes_without_duplicates(hosts in vec(select(vec!["ws://a", "ws://b", "ws://c"]), 0..10), current in any::<bool>()) {
+		let hosts = hosts.into_iter().map(String::from).collect::<Vec<_>>();
+		let nodes = Nodes::new(&hosts);
+
+		let mut expected = vec![];
+		for host in &hosts {
+			if !expected.contains(host) {
+				expected.push(host.clone());
+			}
+		}
+		let listed = nodes.iter().map(|node| node.host.clone()).collect::<Vec<_>>();
+		assert_eq!(listed, expected);
+
+		let current_host = if current { hosts.first().cloned().unwrap_or_default() } else { String::new() };
+		let shuffled = nodes.shuffle(current_host.clone()).into_iter().map(|node| node.host).collect::<Vec<_>>();
+		let unique = shuffled.iter().collect::<HashSet<_>>();
+		assert_eq!(unique.len(), shuffled.len());
+		if expected.len() > 1 {
+			assert!(!shuffled.contains(&current_host));
+		}
+	}
 	}
 }
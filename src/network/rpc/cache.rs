@@ -0,0 +1,144 @@
+use avail_subxt::{primitives::Header, utils::H256};
+use lru::LruCache;
+use std::{
+	num::NonZeroUsize,
+	sync::atomic::{AtomicU64, Ordering},
+};
+use tokio::sync::Mutex;
+
+struct Caches {
+	headers: LruCache<H256, Header>,
+	hashes: LruCache<u32, H256>,
+}
+
+/// Cache of recently fetched block headers, with least recently used entries evicted first.
+///
+/// Headers are indexed by block hash, with additional block number to hash index.
+pub struct HeaderCache {
+	caches: Mutex<Caches>,
+	hits: AtomicU64,
+	misses: AtomicU64,
+}
+
+impl HeaderCache {
+	pub fn new(capacity: NonZeroUsize) -> Self {
+		Self {
+			caches: Mutex::new(Caches {
+				headers: LruCache::new(capacity),
+				hashes: LruCache::new(capacity),
+			}),
+			hits: AtomicU64::new(0),
+			misses: AtomicU64::new(0),
+		}
+	}
+
+	fn count(&self, found: bool) {
+		let counter = if found { &self.hits } else { &self.misses };
+		counter.fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Returns cached header with given hash
+	pub async fn get(&self, hash: &H256) -> Option<Header> {
+		let header = self.caches.lock().await.headers.get(hash).cloned();
+		self.count(header.is_some());
+		header
+	}
+
+	/// Returns cached header and its hash for the given block number
+	pub async fn get_by_number(&self, block_number: u32) -> Option<(Header, H256)> {
+		let mut caches = self.caches.lock().await;
+		let result = caches.hashes.get(&block_number).copied().and_then(|hash| {
+			caches
+				.headers
+				.get(&hash)
+				.cloned()
+				.map(|header| (header, hash))
+		});
+		self.count(result.is_some());
+		result
+	}
+
+	/// Inserts header into the cache, evicting least recently used one if cache is full
+	pub async fn insert(&self, hash: H256, header: Header) {
+		let mut caches = self.caches.lock().await;
+		caches.hashes.put(header.number, hash);
+		caches.headers.put(hash, header);
+	}
+
+	/// Returns ratio of cache hits in all lookups, or `None` if there were no lookups
+	pub fn hit_rate(&self) -> Option<f64> {
+		let hits = self.hits.load(Ordering::Relaxed);
+		let misses = self.misses.load(Ordering::Relaxed);
+		let total = hits + misses;
+		(total > 0).then(|| hits as f64 / total as f64)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::HeaderCache;
+	use avail_subxt::{
+		api::runtime_types::avail_core::{
+			data_lookup::compact::CompactDataLookup,
+			header::extension::{v3::HeaderExtension, HeaderExtension::V3},
+			kate_commitment::v3::KateCommitment,
+		},
+		config::substrate::Digest,
+		primitives::Header,
+		utils::H256,
+	};
+	use std::num::NonZeroUsize;
+
+	fn header(number: u32) -> Header {
+		Header {
+			parent_hash: H256::zero(),
+			number,
+			state_root: H256::zero(),
+			extrinsics_root: H256::zero(),
+			digest: Digest { logs: vec![] },
+			extension: V3(HeaderExtension {
+				commitment: KateCommitment {
+					rows: 1,
+					cols: 4,
+					data_root: H256::zero(),
+					commitment: vec![],
+				},
+				app_lookup: CompactDataLookup {
+					size: 1,
+					index: vec![],
+				},
+			}),
+		}
+	}
+
+	#[tokio::test]
+	async fn test_header_cache() {
+		let cache = HeaderCache::new(NonZeroUsize::new(2).unwrap());
+		assert_eq!(cache.hit_rate(), None);
+
+		let (hash_1, hash_2, hash_3) = (
+			H256::repeat_byte(1),
+			H256::repeat_byte(2),
+			H256::repeat_byte(3),
+		);
+		cache.insert(hash_1, header(1)).await;
+		cache.insert(hash_2, header(2)).await;
+
+		assert_eq!(
+			cache.get(&hash_1).await.map(|header| header.number),
+			Some(1)
+		);
+		assert_eq!(
+			cache.get_by_number(2).await.map(|(_, hash)| hash),
+			Some(hash_2)
+		);
+
+		// Block 1 is least recently used, so it gets evicted
+		cache.insert(hash_3, header(3)).await;
+		assert!(cache.get(&hash_1).await.is_none());
+		assert!(cache.get_by_number(1).await.is_none());
+		assert!(cache.get_by_number(3).await.is_some());
+
+		assert_eq!(cache.hit_rate(), Some(0.6));
+	}
+}
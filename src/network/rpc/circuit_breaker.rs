@@ -0,0 +1,283 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// Configuration for a `CircuitBreaker`.
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerConfig {
+	/// Number of consecutive failures after which the circuit opens, must be at least 1
+	pub failure_threshold: u32,
+	/// Duration for which requests are not allowed after the circuit opens
+	pub recovery_timeout: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+	fn default() -> Self {
+		Self {
+			failure_threshold: 5,
+			recovery_timeout: Duration::from_secs(30),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CircuitState {
+	/// Requests are allowed
+	Closed,
+	/// Requests are not allowed until recovery timeout elapses
+	Open,
+	/// Recovery timeout elapsed, single request is allowed to check if the node recovered
+	HalfOpen,
+}
+
+/// Stops sending requests to the failing node for some time.
+///
+/// Circuit opens after configured number of consecutive failures, and stays open until
+/// recovery timeout elapses. After that, circuit is half-open and a single probe request decides
+/// if the circuit closes (on success) or opens again (on failure).
+#[derive(Debug)]
+pub struct CircuitBreaker {
+	config: CircuitBreakerConfig,
+	consecutive_failures: u32,
+	opened_at: Option<Instant>,
+	/// Start of the probe request in the half-open state
+	probe_started_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+	pub fn new(config: CircuitBreakerConfig) -> Self {
+		Self {
+			config,
+			consecutive_failures: 0,
+			opened_at: None,
+			probe_started_at: None,
+		}
+	}
+
+	pub fn state(&self) -> CircuitState {
+		match self.opened_at {
+			None => CircuitState::Closed,
+			Some(opened_at) if opened_at.elapsed() < self.config.recovery_timeout => {
+				CircuitState::Open
+			},
+			Some(_) => CircuitState::HalfOpen,
+		}
+	}
+
+	/// Checks if the request is allowed in the current state.
+	/// In the half-open state, only the first request is allowed as a probe. Another probe
+	/// is allowed if the outcome of the previous one isn't recorded within the recovery timeout.
+	pub fn try_acquire(&mut self) -> bool {
+		match self.state() {
+			CircuitState::Closed => true,
+			CircuitState::Open => false,
+			CircuitState::HalfOpen => {
+				let probe_pending = self
+					.probe_started_at
+					.is_some_and(|started_at| started_at.elapsed() < self.config.recovery_timeout);
+				if !probe_pending {
+					self.probe_started_at = Some(Instant::now());
+				}
+				!probe_pending
+			},
+		}
+	}
+
+	pub fn record_success(&mut self) {
+		self.consecutive_failures = 0;
+		self.opened_at = None;
+		self.probe_started_at = None;
+	}
+
+	/// Records failed request, circuit is opened only if `can_open` is set.
+	pub fn record_failure(&mut self, can_open: bool) {
+		self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+		self.probe_started_at = None;
+		if !can_open {
+			self.opened_at = None;
+			return;
+		}
+		let reopen = self.state() == CircuitState::HalfOpen;
+		if reopen || self.consecutive_failures >= self.config.failure_threshold.max(1) {
+			self.opened_at = Some(Instant::now());
+		}
+	}
+}
+
+/// Circuit breakers of all RPC nodes, identified by host.
+///
+/// Circuit of the last node which is not open is never opened,
+/// so there is always at least one node to connect to.
+#[derive(Debug)]
+pub struct CircuitBreakers {
+	config: CircuitBreakerConfig,
+	node_count: usize,
+	breakers: Mutex<HashMap<String, CircuitBreaker>>,
+}
+
+impl CircuitBreakers {
+	pub fn new(config: CircuitBreakerConfig, node_count: usize) -> Self {
+		Self {
+			config,
+			node_count,
+			breakers: Default::default(),
+		}
+	}
+
+	/// Checks if request to the given host is allowed (see [`CircuitBreaker::try_acquire`])
+	pub fn try_acquire(&self, host: &str) -> bool {
+		let mut breakers = self.breakers.lock().unwrap();
+		breakers
+			.get_mut(host)
+			.map_or(true, CircuitBreaker::try_acquire)
+	}
+
+	pub fn record_success(&self, host: &str) {
+		let mut breakers = self.breakers.lock().unwrap();
+		if let Some(breaker) = breakers.get_mut(host) {
+			if breaker.state() != CircuitState::Closed {
+				info!(host, "RPC node recovered, closing circuit");
+			}
+			breaker.record_success();
+		}
+	}
+
+	pub fn record_failure(&self, host: &str) {
+		let mut breakers = self.breakers.lock().unwrap();
+		let other_open = breakers
+			.iter()
+			.filter(|(other, breaker)| *other != host && breaker.state() == CircuitState::Open)
+			.count();
+		let can_open = other_open + 1 < self.node_count;
+		let breaker = breakers
+			.entry(host.to_string())
+			.or_insert_with(|| CircuitBreaker::new(self.config));
+		breaker.record_failure(can_open);
+		if breaker.state() == CircuitState::Open {
+			warn!(
+				host,
+				recovery_timeout = ?self.config.recovery_timeout,
+				"RPC node keeps failing, opening circuit"
+			);
+		}
+	}
+
+	/// Returns number of hosts with open circuit
+	pub fn open_count(&self) -> usize {
+		let breakers = self.breakers.lock().unwrap();
+		breakers
+			.values()
+			.filter(|breaker| breaker.state() == CircuitState::Open)
+			.count()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakers, CircuitState};
+	use std::time::Duration;
+	use tokio::time;
+
+	const CONFIG: CircuitBreakerConfig = CircuitBreakerConfig {
+		failure_threshold: 2,
+		recovery_timeout: Duration::from_secs(10),
+	};
+
+	#[tokio::test(start_paused = true)]
+	async fn test_circuit_breaker_states() {
+		let mut breaker = CircuitBreaker::new(CONFIG);
+		assert_eq!(breaker.state(), CircuitState::Closed);
+
+		breaker.record_failure(true);
+		assert_eq!(breaker.state(), CircuitState::Closed);
+		breaker.record_failure(true);
+		assert_eq!(breaker.state(), CircuitState::Open);
+		assert!(!breaker.try_acquire());
+
+		time::advance(Duration::from_secs(10)).await;
+		assert_eq!(breaker.state(), CircuitState::HalfOpen);
+		assert!(breaker.try_acquire());
+
+		// Failure in half-open state opens the circuit again
+		breaker.record_failure(true);
+		assert_eq!(breaker.state(), CircuitState::Open);
+
+		time::advance(Duration::from_secs(10)).await;
+		breaker.record_success();
+		assert_eq!(breaker.state(), CircuitState::Closed);
+
+		// Success resets consecutive failures
+		breaker.record_failure(true);
+		assert_eq!(breaker.state(), CircuitState::Closed);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_single_half_open_probe() {
+		let mut breaker = CircuitBreaker::new(CONFIG);
+		breaker.record_failure(true);
+		breaker.record_failure(true);
+
+		time::advance(Duration::from_secs(10)).await;
+		assert!(breaker.try_acquire());
+		// Concurrent requests are not allowed while the probe is pending
+		assert!(!breaker.try_acquire());
+
+		// Probe without the recorded outcome doesn't block requests forever
+		time::advance(Duration::from_secs(10)).await;
+		assert!(breaker.try_acquire());
+		breaker.record_success();
+		assert!(breaker.try_acquire());
+		assert!(breaker.try_acquire());
+	}
+
+	#[test]
+	fn test_circuit_stays_closed() {
+		let mut breaker = CircuitBreaker::new(CONFIG);
+		for _ in 0..10 {
+			breaker.record_failure(false);
+		}
+		assert_eq!(breaker.state(), CircuitState::Closed);
+
+		// Zero threshold is treated as one
+		let config = CircuitBreakerConfig {
+			failure_threshold: 0,
+			..CONFIG
+		};
+		let mut breaker = CircuitBreaker::new(config);
+		assert!(breaker.try_acquire());
+		breaker.record_failure(true);
+		assert_eq!(breaker.state(), CircuitState::Open);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_circuit_breakers() {
+		let breakers = CircuitBreakers::new(CONFIG, 2);
+		assert!(breakers.try_acquire("ws://a"));
+
+		breakers.record_failure("ws://a");
+		breakers.record_failure("ws://a");
+		breakers.record_failure("ws://b");
+		assert!(!breakers.try_acquire("ws://a"));
+		assert!(breakers.try_acquire("ws://b"));
+		assert_eq!(breakers.open_count(), 1);
+
+		// Circuit of the last available node is not opened
+		breakers.record_failure("ws://b");
+		assert!(breakers.try_acquire("ws://b"));
+		assert_eq!(breakers.open_count(), 1);
+
+		time::advance(Duration::from_secs(10)).await;
+		assert!(breakers.try_acquire("ws://a"));
+		assert_eq!(breakers.open_count(), 0);
+	}
+
+	#[test]
+	fn test_single_node_circuit_never_opens() {
+		let breakers = CircuitBreakers::new(CONFIG, 1);
+		for _ in 0..10 {
+			breakers.record_failure("ws://a");
+		}
+		assert!(breakers.try_acquire("ws://a"));
+		assert_eq!(breakers.open_count(), 0);
+	}
+}
@@ -13,10 +13,18 @@ use color_eyre::{
 	eyre::{eyre, Context},
 	Report, Result,
 };
-use futures::{Stream, TryFutureExt, TryStreamExt};
+use futures::{future, stream, Stream, TryFutureExt, TryStreamExt};
 use kate_recovery::{data::Cell, matrix::Position};
 use sp_core::{bytes::from_hex, ed25519::Public, U256};
-use std::sync::{Arc, Mutex};
+use std::{
+	future::Future,
+	ops::Range,
+	sync::{
+		atomic::{AtomicU32, Ordering},
+		Arc, Mutex,
+	},
+	time::{Duration, Instant},
+};
 use subxt::{
 	backend::{
 		legacy::rpc_methods::{BlockNumber, StorageKey},
@@ -27,12 +35,15 @@ use subxt::{
 	utils::AccountId32,
 };
 use subxt_signer::sr25519::Keypair;
-use tokio::sync::RwLock;
+use tokio::{sync::RwLock, time};
 use tokio_retry::Retry;
 use tokio_stream::StreamExt;
-use tracing::{info, warn};
+use tracing::{debug, info, instrument, warn};
 
-use super::{Node, Nodes, Subscription, WrappedProof};
+use super::{
+	CircuitBreakers, HeaderCache, Node, Nodes, RpcError, Subscription, WrappedProof,
+	CELL_WITH_PROOF_SIZE, PROOF_SIZE,
+};
 use crate::{
 	api::v2::types::Base64,
 	consts::ExpectedNodeVariant,
@@ -40,46 +51,243 @@ use crate::{
 	types::{RetryConfig, State, DEV_FLAG_GENHASH},
 };
 
+/// Fetches system version of the node behind the given client.
+///
+/// Unlike [`Client::get_system_version`], there are no retries, node switching
+/// or genesis hash checks, which makes it suitable for health checks and monitoring
+/// of a specific node. Use [`Client`] for everything else.
+pub async fn get_system_version(client: &AvailClient) -> Result<String, RpcError> {
+	client
+		.legacy_rpc()
+		.system_version()
+		.await
+		.wrap_err("get_system_version: failed to fetch system version")
+		.map_err(RpcError::from)
+}
+
+/// Returns runtime version of the node behind the given client (see [`get_system_version`]).
+pub fn get_runtime_version(client: &AvailClient) -> RuntimeVersion {
+	client.runtime_version()
+}
+
+/// Outcome of the health check of a single full node
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeHealthReport {
+	pub url: String,
+	pub reachable: bool,
+	pub version: Option<String>,
+	pub version_matches: bool,
+	/// Duration of the system version request, in milliseconds
+	pub latency_ms: Option<u64>,
+}
+
+impl NodeHealthReport {
+	pub fn is_healthy(&self) -> bool {
+		self.reachable && self.version_matches
+	}
+}
+
+/// Checks reachability and system version of all given full nodes concurrently.
+/// Like [`get_system_version`], genesis hash is not checked and there are no retries.
+/// Failure of one node doesn't stop the check of the others.
+/// Node which doesn't respond within the timeout is reported as unreachable.
+pub async fn health_check_nodes(
+	full_nodes: &[String],
+	expected_node: &ExpectedNodeVariant,
+	timeout: Duration,
+) -> Vec<NodeHealthReport> {
+	let checks = full_nodes.iter().map(|url| async move {
+		let check = async {
+			let client = AvailClient::new_insecure(url)
+				.await
+				.map_err(|e| eyre!(e))
+				.wrap_err_with(|| format!("health_check_nodes: failed to connect to {url}"))?;
+			let begin = Instant::now();
+			let version = get_system_version(&client).await?;
+			Ok::<_, Report>((version, begin.elapsed()))
+		};
+		let result = time::timeout(timeout, check)
+			.await
+			.unwrap_or_else(|_| Err(eyre!("Health check timed out after {timeout:?}")));
+
+		match result {
+			Ok((version, latency)) => NodeHealthReport {
+				url: url.clone(),
+				reachable: true,
+				version_matches: expected_node.matches(&version),
+				version: Some(version),
+				latency_ms: Some(latency.as_millis() as u64),
+			},
+			Err(error) => {
+				warn!(url, "Node health check failed: {error:#}");
+				NodeHealthReport {
+					url: url.clone(),
+					reachable: false,
+					version: None,
+					version_matches: false,
+					latency_ms: None,
+				}
+			},
+		}
+	});
+	future::join_all(checks).await
+}
+
+/// Returns full nodes which accept the WebSocket connection within the timeout.
+/// Nodes are probed concurrently and without retries.
+pub async fn reachable_nodes(full_nodes: &[String], timeout: Duration) -> Vec<String> {
+	probe_nodes(full_nodes, timeout, |url| {
+		AvailClient::new_insecure(url)
+			.map_ok(|_| ())
+			.map_err(|error| eyre!(error))
+	})
+	.await
+}
+
+/// Returns nodes for which the probe succeeds within the timeout, in the given order
+async fn probe_nodes<'a, F, Fut>(
+	full_nodes: &'a [String],
+	timeout: Duration,
+	probe: F,
+) -> Vec<String>
+where
+	F: Fn(&'a str) -> Fut,
+	Fut: Future<Output = Result<()>>,
+{
+	let probe = &probe;
+	let probes = full_nodes.iter().map(|url| async move {
+		match time::timeout(timeout, probe(url)).await {
+			Ok(Ok(())) => Some(url.clone()),
+			Ok(Err(error)) => {
+				warn!(url, "Full node is not reachable: {error:#}");
+				None
+			},
+			Err(_) => {
+				warn!(url, ?timeout, "Full node connection timed out");
+				None
+			},
+		}
+	});
+	future::join_all(probes)
+		.await
+		.into_iter()
+		.flatten()
+		.collect()
+}
+
+/// Checks if the error is caused by the connection to the node, rather than by the request itself
+/// (e.g. missing block or decoding failure). Only connection errors are recorded by circuit breakers.
+fn is_connection_error(error: &Report) -> bool {
+	error.chain().any(|cause| {
+		if let Some(error) = cause.downcast_ref::<subxt::Error>() {
+			return matches!(
+				error,
+				subxt::Error::Io(_)
+					| subxt::Error::Rpc(
+						subxt::error::RpcError::ClientError(_)
+							| subxt::error::RpcError::SubscriptionDropped
+					)
+			);
+		}
+		if let Some(error) = cause.downcast_ref::<RpcError>() {
+			return matches!(error, RpcError::ConnectionFailed(_));
+		}
+		cause.is::<std::io::Error>()
+	})
+}
+
+/// Maps errors of internal helpers to [`RpcError`] at the public API boundary.
+/// Typed errors are preserved, connection errors are reported as [`RpcError::ConnectionFailed`]
+/// and other failures as [`RpcError::RequestFailed`], with the full error chain as the message.
+impl From<Report> for RpcError {
+	fn from(report: Report) -> Self {
+		if let Some(error) = report.downcast_ref::<RpcError>() {
+			return error.clone();
+		}
+		let error = format!("{report:#}");
+		if is_connection_error(&report) {
+			RpcError::ConnectionFailed(error)
+		} else {
+			RpcError::RequestFailed(error)
+		}
+	}
+}
+
+/// Statistics of the initial connection to the full node
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConnectionResult {
+	/// Number of nodes connection was attempted to, including the connected one
+	pub nodes_tried: u32,
+	/// Number of retries after all nodes failed
+	pub retries: u32,
+}
+
 #[derive(Clone)]
 pub struct Client {
 	subxt_client: Arc<RwLock<Arc<AvailClient>>>,
 	state: Arc<Mutex<State>>,
 	nodes: Nodes,
+	connection_result: ConnectionResult,
 	retry_config: RetryConfig,
+	max_backoff: Duration,
+	header_cache: Arc<HeaderCache>,
+	circuit_breakers: Arc<CircuitBreakers>,
 	expected_genesis_hash: String,
 	shutdown: Controller<String>,
 }
 
 impl Client {
+	#[allow(clippy::too_many_arguments)]
 	pub async fn new(
 		state: Arc<Mutex<State>>,
 		nodes: Nodes,
 		expected_genesis_hash: &str,
 		retry_config: RetryConfig,
+		max_backoff: Duration,
+		header_cache: HeaderCache,
+		circuit_breakers: CircuitBreakers,
 		shutdown: Controller<String>,
-	) -> Result<Self> {
+	) -> Result<Self, RpcError> {
+		let attempts = AtomicU32::new(0);
+		let nodes_tried = AtomicU32::new(0);
 		// try and connect appropriate Node from the provided list
 		// will do retries with the provided Retry Config
 		let (client, node, _) = match shutdown
-			.with_cancel(Retry::spawn(retry_config.clone(), || async {
-				Self::try_connect_and_execute(
-					nodes.shuffle(Default::default()),
-					ExpectedNodeVariant::default(),
-					expected_genesis_hash,
-					|_| futures::future::ok(()),
-				)
-				.await
-			}))
+			.with_cancel(Retry::spawn(
+				retry_config.clone().bounded(max_backoff),
+				|| async {
+					attempts.fetch_add(1, Ordering::Relaxed);
+					Self::try_connect_and_execute(
+						nodes.shuffle(Default::default()),
+						ExpectedNodeVariant::default(),
+						expected_genesis_hash,
+						&nodes_tried,
+						&circuit_breakers,
+						|_| futures::future::ok(()),
+					)
+					.await
+				},
+			))
 			.await
 		{
 			Ok(result) => result?,
 			Err(err) => {
-				return Err(eyre!(
+				return Err(RpcError::ConnectionFailed(format!(
 					"RPC Client creation Retry strategy halted due to shutdown: {err}"
-				))
+				)))
 			},
 		};
 
+		let connection_result = ConnectionResult {
+			nodes_tried: nodes_tried.into_inner(),
+			retries: attempts.into_inner().saturating_sub(1),
+		};
+		info!(
+			nodes_tried = connection_result.nodes_tried,
+			retries = connection_result.retries,
+			"Connected to the full node"
+		);
+
 		// update application wide State with the newly connected Node
 		state.lock().unwrap().connected_node = node;
 
@@ -87,7 +295,11 @@ impl Client {
 			subxt_client: Arc::new(RwLock::new(client)),
 			state,
 			nodes,
+			connection_result,
 			retry_config,
+			max_backoff,
+			header_cache: Arc::new(header_cache),
+			circuit_breakers: Arc::new(circuit_breakers),
 			expected_genesis_hash: expected_genesis_hash.to_string(),
 			shutdown,
 		})
@@ -100,7 +312,8 @@ impl Client {
 	) -> Result<(AvailClient, Node)> {
 		let client = AvailClient::new_insecure(host)
 			.await
-			.map_err(|e| eyre!(e))?;
+			.map_err(|e| eyre!(e))
+			.wrap_err_with(|| format!("create_subxt_client: failed to connect to {host}"))?;
 
 		// check genesis hash
 		let genesis_hash = client.genesis_hash();
@@ -124,15 +337,17 @@ impl Client {
 		};
 
 		// check system and runtime versions
-		let system_version = client.legacy_rpc().system_version().await?;
-		let runtime_version: RuntimeVersion = client.runtime_version();
+		let system_version = get_system_version(&client)
+			.await
+			.wrap_err_with(|| format!("create_subxt_client: node {host}"))?;
+		let runtime_version = get_runtime_version(&client);
 
 		if !expected_node.matches(&system_version) {
-			return Err(eyre!(
-				"Expected Node system version:{:?}, found: {}. Skipping to another node.",
-				expected_node.system_version,
-				system_version,
-			));
+			return Err(RpcError::VersionMismatch {
+				expected: format!("{:?}", expected_node.system_version),
+				found: system_version,
+			})
+			.wrap_err("Skipping to another node");
 		}
 
 		let variant = Node::new(
@@ -149,6 +364,8 @@ impl Client {
 		nodes: Vec<Node>,
 		expected_node: ExpectedNodeVariant,
 		expected_genesis_hash: &str,
+		nodes_tried: &AtomicU32,
+		circuit_breakers: &CircuitBreakers,
 		mut f: F,
 	) -> Result<(Arc<AvailClient>, Node, T)>
 	where
@@ -158,6 +375,11 @@ impl Client {
 		// go through the provided list of Nodes to try and find and appropriate one,
 		// after a successful connection, try to execute passed function call
 		for Node { host, .. } in nodes.iter() {
+			if !circuit_breakers.try_acquire(host) {
+				debug!(host, "Skipping node with open circuit");
+				continue;
+			}
+			nodes_tried.fetch_add(1, Ordering::Relaxed);
 			let result =
 				Self::create_subxt_client(host, expected_node.clone(), expected_genesis_hash)
 					.and_then(move |(client, node)| {
@@ -167,12 +389,23 @@ impl Client {
 					.await;
 
 			match result {
-				Err(error) => warn!(host, %error, "Skipping connection with this node"),
-				ok => return ok,
+				Err(error) => {
+					if is_connection_error(&error) {
+						circuit_breakers.record_failure(host);
+					}
+					warn!(host, %error, "Skipping connection with this node")
+				},
+				ok => {
+					circuit_breakers.record_success(host);
+					return ok;
+				},
 			}
 		}
 
-		Err(eyre!("Failed to connect any appropriate working node"))
+		Err(RpcError::ConnectionFailed(
+			"Failed to connect any appropriate working node".to_string(),
+		)
+		.into())
 	}
 
 	async fn with_retries<F, Fut, T>(&self, mut f: F) -> Result<T>
@@ -184,8 +417,20 @@ impl Client {
 		match self
 			.shutdown
 			.with_cancel(Retry::spawn(
-				self.retry_config.clone(),
-				move || async move { f(self.current_client().await).await },
+				self.retry_config.clone().bounded(self.max_backoff),
+				move || async move {
+					let host = self.state.lock().unwrap().connected_node.host.clone();
+					let result = f(self.current_client().await).await;
+					match &result {
+						Ok(_) => self.circuit_breakers.record_success(&host),
+						Err(error) if is_connection_error(error) => {
+							self.circuit_breakers.record_failure(&host)
+						},
+						// Failed request doesn't mean that the node is failing
+						Err(_) => {},
+					}
+					result
+				},
 			))
 			.await
 		{
@@ -208,19 +453,25 @@ impl Client {
 		);
 		// shuffle nodes, if possible
 		let nodes = self.nodes.shuffle(connected_node.host);
+		let nodes_tried = AtomicU32::new(0);
 		// go through available Nodes, try to connect, Retry connecting if needed
 		let (client, node, result) = match self
 			.shutdown
-			.with_cancel(Retry::spawn(self.retry_config.clone(), || async {
-				let nodes = nodes.clone();
-				Self::try_connect_and_execute(
-					nodes,
-					ExpectedNodeVariant::default(),
-					&self.expected_genesis_hash,
-					move |client| f(client).map_err(Report::from),
-				)
-				.await
-			}))
+			.with_cancel(Retry::spawn(
+				self.retry_config.clone().bounded(self.max_backoff),
+				|| async {
+					let nodes = nodes.clone();
+					Self::try_connect_and_execute(
+						nodes,
+						ExpectedNodeVariant::default(),
+						&self.expected_genesis_hash,
+						&nodes_tried,
+						&self.circuit_breakers,
+						move |client| f(client).map_err(Report::from),
+					)
+					.await
+				},
+			))
 			.await
 		{
 			Ok(res) => res?,
@@ -242,7 +493,11 @@ impl Client {
 		client: Arc<AvailClient>,
 	) -> Result<impl Stream<Item = Result<Subscription, subxt::error::Error>>> {
 		// create Header subscription
-		let header_subscription = client.backend().stream_finalized_block_headers().await?;
+		let header_subscription = client
+			.backend()
+			.stream_finalized_block_headers()
+			.await
+			.wrap_err("create_subxt_subscriptions: failed to subscribe to finalized headers")?;
 		// map Header subscription to the same type for later matching
 		let headers = header_subscription.map_ok(|(header, _)| Subscription::Header(header));
 
@@ -253,14 +508,15 @@ impl Client {
 				rpc_params![],
 				"grandpa_unsubscribeJustifications",
 			)
-			.await?;
+			.await
+			.wrap_err("create_subxt_subscriptions: failed to subscribe to justifications")?;
 		// map Justification subscription to the same type for later matching
 		let justifications = justification_subscription.map_ok(Subscription::Justification);
 
 		Ok(headers.merge(justifications))
 	}
 
-	pub async fn subscription_stream(self) -> impl Stream<Item = Result<Subscription>> {
+	pub async fn subscription_stream(self) -> impl Stream<Item = Result<Subscription, RpcError>> {
 		async_stream::stream! {
 			'outer: loop{
 				let mut stream = match self.with_retries(|client| async move{
@@ -269,7 +525,7 @@ impl Client {
 				}).await {
 					Ok(s) => s,
 					Err(err) => {
-						yield Err(err);
+						yield Err(RpcError::from(err));
 						return;
 					}
 				};
@@ -297,7 +553,23 @@ impl Client {
 		self.subxt_client.read().await.clone()
 	}
 
-	pub async fn get_block_hash(&self, block_number: u32) -> Result<H256> {
+	/// Returns statistics of the initial connection to the full node
+	pub fn connection_result(&self) -> ConnectionResult {
+		self.connection_result
+	}
+
+	/// Returns circuit breakers of the RPC nodes
+	pub fn circuit_breakers(&self) -> Arc<CircuitBreakers> {
+		self.circuit_breakers.clone()
+	}
+
+	/// Returns cache of recently fetched block headers
+	pub fn header_cache(&self) -> Arc<HeaderCache> {
+		self.header_cache.clone()
+	}
+
+	#[instrument(skip_all, fields(block_number = block_number), level = "trace")]
+	pub async fn get_block_hash(&self, block_number: u32) -> Result<H256, RpcError> {
 		let hash = self
 			.with_retries(|client| async move {
 				client
@@ -306,33 +578,32 @@ impl Client {
 					.await
 					.map_err(Into::into)
 			})
-			.await?
-			.ok_or_else(|| eyre!("Block with number: {} not found", block_number))?;
+			.await
+			.wrap_err_with(|| format!("get_block_hash: block {block_number}"))?
+			.ok_or(RpcError::BlockNotFound(block_number))?;
 
 		Ok(hash)
 	}
 
-	pub async fn get_header_by_hash(&self, block_hash: H256) -> Result<Header> {
+	#[instrument(skip_all, fields(block_hash = ?block_hash), level = "trace")]
+	pub async fn get_header_by_hash(&self, block_hash: H256) -> Result<Header, RpcError> {
 		self.with_retries(|client| async move {
 			client
 				.backend()
 				.block_header(block_hash)
 				.await?
-				.ok_or_else(|| {
-					subxt::Error::Other(
-						format!("Block Header with hash: {block_hash:?} not found",),
-					)
-				})
-				.map_err(Into::into)
+				.ok_or_else(|| Report::from(RpcError::HeaderNotFound(block_hash)))
 		})
 		.await
-		.wrap_err(format!(
-			"Block Header with hash: {:?} not found",
-			block_hash
-		))
+		.wrap_err_with(|| format!("get_header_by_hash: block hash {block_hash:?}"))
+		.map_err(RpcError::from)
 	}
 
-	pub async fn get_validator_set_by_hash(&self, block_hash: H256) -> Result<Vec<Public>> {
+	#[instrument(skip_all, fields(block_hash = ?block_hash), level = "trace")]
+	pub async fn get_validator_set_by_hash(
+		&self,
+		block_hash: H256,
+	) -> Result<Vec<Public>, RpcError> {
 		let res = self
 			.with_retries(|client| async move {
 				client
@@ -342,7 +613,8 @@ impl Client {
 					.await
 					.map_err(Into::into)
 			})
-			.await?
+			.await
+			.wrap_err_with(|| format!("get_validator_set_by_hash: block hash {block_hash:?}"))?
 			.iter()
 			.map(|e| e.0)
 			.collect();
@@ -350,7 +622,7 @@ impl Client {
 		Ok(res)
 	}
 
-	pub async fn get_finalized_head_hash(&self) -> Result<H256> {
+	pub async fn get_finalized_head_hash(&self) -> Result<H256, RpcError> {
 		let head = self
 			.with_retries(|client| async move {
 				client
@@ -359,22 +631,28 @@ impl Client {
 					.await
 					.map_err(Into::into)
 			})
-			.await?;
+			.await
+			.wrap_err("get_finalized_head_hash: failed to fetch finalized head")?;
 
 		Ok(head)
 	}
 
-	pub async fn get_chain_head_header(&self) -> Result<Header> {
+	pub async fn get_chain_head_header(&self) -> Result<Header, RpcError> {
 		let finalized_hash = self.get_finalized_head_hash().await?;
 		self.get_header_by_hash(finalized_hash).await
 	}
 
-	pub async fn request_kate_rows(
+	/// Queries rows of the block, converting each row of cells with `map_row`.
+	async fn query_rows<T>(
 		&self,
 		rows: Vec<u32>,
 		block_hash: H256,
-	) -> Result<Vec<Vec<u8>>> {
-		let rows = Rows::try_from(rows).unwrap();
+		map_row: impl Fn(&[U256]) -> T,
+	) -> Result<Vec<T>> {
+		let rows_count = rows.len();
+		let rows = Rows::try_from(rows)
+			.map_err(|_| eyre!("query_rows: too many rows ({rows_count}) in a single query"))?;
+		let map_row = &map_row;
 		self.with_retries(|client| {
 			let rows = rows.clone();
 			async move {
@@ -383,40 +661,45 @@ impl Client {
 					.query_rows(rows, block_hash)
 					.await
 					.map_err(|error| subxt::Error::Other(format!("{error}")))?;
-				Ok(rows
-					.iter()
-					.map(|row| {
-						row.iter()
-							.flat_map(|cell| {
-								let mut bytes = [0u8; 32];
-								cell.to_big_endian(&mut bytes);
-								bytes.to_vec()
-							})
-							.collect()
-					})
-					.collect())
+				Ok(rows.iter().map(|row| map_row(row)).collect())
 			}
 		})
 		.await
+		.wrap_err_with(|| format!("query_rows: {rows_count} rows of block hash {block_hash:?}"))
 	}
 
-	pub async fn request_kate_proof(
+	#[instrument(skip_all, fields(block_hash = ?block_hash), level = "trace")]
+	pub async fn request_kate_rows(
 		&self,
+		rows: Vec<u32>,
 		block_hash: H256,
-		positions: &[Position],
-	) -> Result<Vec<Cell>> {
-		fn concat_content(scalar: U256, proof: GProof) -> Result<[u8; 80]> {
-			let proof: Vec<u8> = proof.into();
-			if proof.len() != 48 {
-				return Err(eyre!("Invalid proof length"));
-			}
+	) -> Result<Vec<Vec<u8>>, RpcError> {
+		Ok(self.query_rows(rows, block_hash, row_bytes).await?)
+	}
 
-			let mut result = [0u8; 80];
-			scalar.to_big_endian(&mut result[48..]);
-			result[..48].copy_from_slice(&proof);
-			Ok(result)
-		}
+	/// Fetches rows like [`Client::request_kate_rows`], keeping only the columns in `col_range`.
+	/// RPC doesn't support partial rows, so full rows are fetched and filtered afterwards.
+	/// Partial rows cannot be verified against the row commitments.
+	pub async fn request_kate_rows_partial(
+		&self,
+		rows: Vec<u32>,
+		col_range: Range<u32>,
+		block_hash: H256,
+	) -> Result<Vec<Vec<u8>>, RpcError> {
+		let rows = self
+			.query_rows(rows, block_hash, |row| {
+				row_bytes(row_columns(row, &col_range))
+			})
+			.await?;
+		Ok(rows)
+	}
 
+	#[instrument(skip_all, fields(block_hash = ?block_hash), level = "trace")]
+	pub async fn request_kate_proof(
+		&self,
+		block_hash: H256,
+		positions: &[Position],
+	) -> Result<Vec<Cell>, RpcError> {
 		let cells: Cells = positions
 			.iter()
 			.map(|p| avail_subxt::Cell {
@@ -425,59 +708,83 @@ impl Client {
 			})
 			.collect::<Vec<_>>()
 			.try_into()
-			.map_err(|_| eyre!("Failed to convert to cells"))?;
+			.map_err(|_| {
+				RpcError::ProofFetchFailed(format!(
+					"request_kate_proof: too many positions ({}) in a single query",
+					positions.len()
+				))
+			})?;
 
-		let proofs: Vec<(GRawScalar, GProof)> = self
-			.with_retries(|client| {
-				let cells = cells.clone();
-				async move {
-					client
-						.rpc_methods()
-						.query_proof(cells, block_hash)
-						.await
-						.map_err(|error| subxt::Error::Other(format!("{error}")))
-						.map_err(Into::into)
-				}
-			})
-			.await
-			.map_err(Report::from)?;
+		// Malformed response is handled as a node failure, so the request is retried on other nodes
+		self.with_retries(|client| {
+			let cells = cells.clone();
+			async move {
+				let proofs: Vec<(GRawScalar, GProof)> = client
+					.rpc_methods()
+					.query_proof(cells, block_hash)
+					.await
+					.map_err(|error| subxt::Error::Other(format!("{error}")))?;
 
-		let contents = proofs
-			.into_iter()
-			.map(|(scalar, proof)| concat_content(scalar, proof).expect("TODO"));
+				let proofs = proofs
+					.into_iter()
+					.map(|(scalar, proof)| (scalar, proof.into()))
+					.collect::<Vec<(U256, Vec<u8>)>>();
 
-		Ok(positions
-			.iter()
-			.zip(contents)
-			.map(|(&position, content)| Cell { position, content })
-			.collect::<Vec<_>>())
+				cells_with_proofs(positions, proofs)
+					.wrap_err("request_kate_proof: invalid proof response")
+			}
+		})
+		.await
+		.map_err(|error| {
+			RpcError::ProofFetchFailed(format!(
+				"request_kate_proof: {} positions of block hash {block_hash:?}: {error:#}",
+				positions.len()
+			))
+		})
 	}
 
-	pub async fn get_system_version(&self) -> Result<String> {
-		let res = self
-			.with_retries(|client| async move {
-				client
-					.legacy_rpc()
-					.system_version()
-					.await
-					.map_err(Into::into)
-			})
-			.await?;
+	/// Requests kate proofs for union of the given position sets of the same block
+	/// (see [`super::fetch_cells_deduplicated`]).
+	pub async fn request_kate_proof_deduplicated(
+		&self,
+		block_hash: H256,
+		position_sets: &[Vec<Position>],
+	) -> Result<Vec<Cell>, RpcError> {
+		super::fetch_cells_deduplicated(position_sets, |positions| async move {
+			self.request_kate_proof(block_hash, &positions).await
+		})
+		.await
+	}
 
-		Ok(res)
+	/// Fetches system version of the connected node, retrying and switching nodes on failure.
+	/// To check a node without the connection flow of [`Client`], use [`get_system_version`].
+	pub async fn get_system_version(&self) -> Result<String, RpcError> {
+		let version = self
+			.with_retries(|client| async move { Ok(get_system_version(&client).await?) })
+			.await?;
+		Ok(version)
 	}
 
-	pub async fn get_runtime_version(&self) -> Result<RuntimeVersion> {
-		self.with_retries(|client| async move { Ok(client.runtime_version()) })
-			.await
+	/// Returns runtime version of the connected node.
+	/// To check a node without the connection flow of [`Client`], use [`get_runtime_version`].
+	pub async fn get_runtime_version(&self) -> Result<RuntimeVersion, RpcError> {
+		let version = self
+			.with_retries(|client| async move { Ok(get_runtime_version(&client)) })
+			.await?;
+		Ok(version)
 	}
 
-	pub async fn get_validator_set_by_block_number(&self, block_num: u32) -> Result<Vec<Public>> {
+	#[instrument(skip_all, fields(block_number = block_num), level = "trace")]
+	pub async fn get_validator_set_by_block_number(
+		&self,
+		block_num: u32,
+	) -> Result<Vec<Public>, RpcError> {
 		let hash = self.get_block_hash(block_num).await?;
 		self.get_validator_set_by_hash(hash).await
 	}
 
-	pub async fn fetch_set_id_at(&self, block_hash: H256) -> Result<u64> {
+	#[instrument(skip_all, fields(block_hash = ?block_hash), level = "trace")]
+	pub async fn fetch_set_id_at(&self, block_hash: H256) -> Result<u64, RpcError> {
 		let res = self
 			.with_retries(|client| {
 				let set_id_key = api::storage().grandpa().current_set_id();
@@ -490,25 +797,67 @@ impl Client {
 						.map_err(Into::into)
 				}
 			})
-			.await?
-			.ok_or_else(|| eyre!("The set_id should exist"))?;
+			.await
+			.wrap_err_with(|| format!("fetch_set_id_at: block hash {block_hash:?}"))?
+			.ok_or_else(|| {
+				RpcError::RequestFailed(format!(
+					"fetch_set_id_at: set_id doesn't exist at block hash {block_hash:?}"
+				))
+			})?;
 
 		Ok(res)
 	}
 
-	pub async fn get_current_set_id_by_block_number(&self, block_num: u32) -> Result<u64> {
+	#[instrument(skip_all, fields(block_number = block_num), level = "trace")]
+	pub async fn get_current_set_id_by_block_number(
+		&self,
+		block_num: u32,
+	) -> Result<u64, RpcError> {
 		let hash = self.get_block_hash(block_num).await?;
 		self.fetch_set_id_at(hash).await
 	}
 
-	pub async fn get_header_by_block_number(&self, block_num: u32) -> Result<(Header, H256)> {
+	#[instrument(skip_all, fields(block_number = block_num), level = "trace")]
+	pub async fn get_header_by_block_number(
+		&self,
+		block_num: u32,
+	) -> Result<(Header, H256), RpcError> {
+		if let Some(cached) = self.header_cache.get_by_number(block_num).await {
+			return Ok(cached);
+		}
+
 		let hash = self.get_block_hash(block_num).await?;
-		self.get_header_by_hash(hash)
+		let header = self.get_header_by_hash(hash).await?;
+		self.header_cache.insert(hash, header.clone()).await;
+		Ok((header, hash))
+	}
+
+	/// Fetches headers for the blocks in the inclusive range, with up to `concurrency` requests in flight.
+	/// Headers which cannot be fetched are returned as `None`, in place of the missing block.
+	#[instrument(skip_all, fields(start = start, end = end), level = "trace")]
+	pub async fn get_headers_in_range(
+		&self,
+		start: u32,
+		end: u32,
+		concurrency: usize,
+	) -> Vec<Option<(Header, H256)>> {
+		let requests = (start..=end).map(|block_number| async move {
+			self.get_header_by_block_number(block_number)
+				.await
+				.map_err(|error| warn!(block_number, "Cannot fetch header: {error:#}"))
+				.ok()
+		});
+
+		futures::StreamExt::buffered(stream::iter(requests), concurrency.max(1))
+			.collect()
 			.await
-			.map(|header| (header, hash))
 	}
 
-	pub async fn get_validator_set_at(&self, block_hash: H256) -> Result<Option<Vec<AccountId32>>> {
+	#[instrument(skip_all, fields(block_hash = ?block_hash), level = "trace")]
+	pub async fn get_validator_set_at(
+		&self,
+		block_hash: H256,
+	) -> Result<Option<Vec<AccountId32>>, RpcError> {
 		let res = self
 			.with_retries(|client| {
 				let validators_key = api::storage().session().validators();
@@ -522,7 +871,7 @@ impl Client {
 				}
 			})
 			.await
-			.map_err(Report::from)?;
+			.wrap_err_with(|| format!("get_validator_set_at: block hash {block_hash:?}"))?;
 
 		Ok(res)
 	}
@@ -532,7 +881,7 @@ impl Client {
 		data: Base64,
 		signer: &Keypair,
 		app_id: AppId,
-	) -> Result<subxt::blocks::ExtrinsicEvents<AvailConfig>> {
+	) -> Result<subxt::blocks::ExtrinsicEvents<AvailConfig>, RpcError> {
 		let data = Arc::new(data);
 		self.with_retries(|client| {
 			let data = data.clone();
@@ -545,12 +894,14 @@ impl Client {
 			}
 		})
 		.await
+		.wrap_err_with(|| format!("submit_signed_and_wait_for_finalized: app {}", app_id.0))
+		.map_err(RpcError::from)
 	}
 
 	pub async fn submit_from_bytes_and_wait_for_finalized(
 		&self,
 		tx_bytes: Vec<u8>,
-	) -> Result<subxt::blocks::ExtrinsicEvents<AvailConfig>> {
+	) -> Result<subxt::blocks::ExtrinsicEvents<AvailConfig>, RpcError> {
 		self.with_retries(|client| {
 			let extrinsic =
 				SubmittableExtrinsic::from_bytes(client.online().clone(), tx_bytes.clone());
@@ -563,14 +914,22 @@ impl Client {
 			}
 		})
 		.await
+		.wrap_err_with(|| {
+			format!(
+				"submit_from_bytes_and_wait_for_finalized: {} bytes",
+				tx_bytes.len()
+			)
+		})
+		.map_err(RpcError::from)
 	}
 
+	#[instrument(skip_all, fields(block_hash = ?hash), level = "trace")]
 	pub async fn get_paged_storage_keys(
 		&self,
 		key: Vec<u8>,
 		count: usize,
 		hash: H256,
-	) -> Result<Vec<StorageKey>> {
+	) -> Result<Vec<StorageKey>, RpcError> {
 		let key = &key;
 		self.with_retries(|client| async move {
 			let storage = client.storage().at(hash);
@@ -582,14 +941,16 @@ impl Client {
 				.map_err(Into::into)
 		})
 		.await
-		.map_err(Report::from)
+		.wrap_err_with(|| format!("get_paged_storage_keys: {count} keys at block hash {hash:?}"))
+		.map_err(RpcError::from)
 	}
 
+	#[instrument(skip_all, fields(block_hash = ?block_hash), level = "trace")]
 	pub async fn get_session_key_owner_at(
 		&self,
 		block_hash: H256,
 		public_key: Public,
-	) -> Result<Option<AccountId32>> {
+	) -> Result<Option<AccountId32>, RpcError> {
 		let res = self
 			.with_retries(|client| {
 				let session_key_key_owner = api::storage().session().key_owner(
@@ -606,12 +967,16 @@ impl Client {
 				}
 			})
 			.await
-			.map_err(Report::from)?;
+			.wrap_err_with(|| format!("get_session_key_owner_at: block hash {block_hash:?}"))?;
 
 		Ok(res)
 	}
 
-	pub async fn request_finality_proof(&self, block_number: u32) -> Result<WrappedProof> {
+	#[instrument(skip_all, fields(block_number = block_number), level = "trace")]
+	pub async fn request_finality_proof(
+		&self,
+		block_number: u32,
+	) -> Result<WrappedProof, RpcError> {
 		let params = rpc_params![block_number]
 			.build()
 			.map(|value| value.get().to_string());
@@ -624,14 +989,252 @@ impl Client {
 					.map_err(Into::into)
 			})
 			.await
-			.map_err(|e| eyre!("Request failed at Finality Proof. Error: {e}"))?;
+			.wrap_err_with(|| format!("request_finality_proof: block {block_number}"))?;
 
 		Ok(res)
 	}
 
-	pub async fn get_genesis_hash(&self) -> Result<H256> {
+	pub async fn get_genesis_hash(&self) -> Result<H256, RpcError> {
 		let gen_hash = self.current_client().await.genesis_hash();
 
 		Ok(gen_hash)
 	}
 }
+
+/// Concatenates big endian bytes of the row cells.
+fn row_bytes(row: &[U256]) -> Vec<u8> {
+	row.iter()
+		.flat_map(|cell| {
+			let mut bytes = [0u8; 32];
+			cell.to_big_endian(&mut bytes);
+			bytes.to_vec()
+		})
+		.collect()
+}
+
+/// Returns row cells in the given column range, the range is clamped to the row length.
+fn row_columns<'a, T>(row: &'a [T], col_range: &Range<u32>) -> &'a [T] {
+	let end = (col_range.end as usize).min(row.len());
+	let start = (col_range.start as usize).min(end);
+	&row[start..end]
+}
+
+/// Verifies that node responded with a proof for each requested position.
+fn verify_lengths(positions_len: usize, proofs_len: usize) -> Result<()> {
+	if positions_len != proofs_len {
+		return Err(eyre!(
+			"Expected {positions_len} proofs, but {proofs_len} proofs received"
+		));
+	}
+	Ok(())
+}
+
+/// Concatenates proof and scalar into cell content.
+fn cell_content(scalar: U256, proof: Vec<u8>) -> Result<[u8; CELL_WITH_PROOF_SIZE]> {
+	if proof.len() != PROOF_SIZE {
+		return Err(eyre!("Invalid proof length: {}", proof.len()));
+	}
+
+	let mut result = [0u8; CELL_WITH_PROOF_SIZE];
+	scalar.to_big_endian(&mut result[PROOF_SIZE..]);
+	result[..PROOF_SIZE].copy_from_slice(&proof);
+	Ok(result)
+}
+
+fn cells_with_proofs(positions: &[Position], proofs: Vec<(U256, Vec<u8>)>) -> Result<Vec<Cell>> {
+	verify_lengths(positions.len(), proofs.len()).wrap_err("cells_with_proofs")?;
+
+	positions
+		.iter()
+		.zip(proofs)
+		.map(|(&position, (scalar, proof))| {
+			cell_content(scalar, proof)
+				.map(|content| Cell { position, content })
+				.wrap_err_with(|| format!("cells_with_proofs: position {position:?}"))
+		})
+		.collect::<Result<Vec<_>>>()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tokio::{
+		io::{AsyncReadExt, AsyncWriteExt},
+		net::{TcpListener, TcpStream},
+	};
+	use warp::Filter;
+
+	#[test]
+	fn test_is_connection_error() {
+		let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+		let error = Report::new(subxt::Error::Io(reset)).wrap_err("Failed to fetch header");
+		assert!(is_connection_error(&error));
+		let dropped = subxt::Error::Rpc(subxt::error::RpcError::SubscriptionDropped);
+		assert!(is_connection_error(&Report::new(dropped)));
+		let failed = RpcError::ConnectionFailed("No nodes".to_string());
+		assert!(is_connection_error(&Report::new(failed)));
+
+		assert!(!is_connection_error(&Report::new(RpcError::BlockNotFound(
+			1
+		))));
+		let decoding = subxt::Error::Other("Invalid proof".to_string());
+		assert!(!is_connection_error(&Report::new(decoding)));
+	}
+
+	#[test]
+	fn test_rpc_error_from_report() {
+		let not_found = Report::new(RpcError::BlockNotFound(1)).wrap_err("get_block_hash: block 1");
+		assert_eq!(RpcError::from(not_found), RpcError::BlockNotFound(1));
+
+		let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+		let error = Report::new(subxt::Error::Io(reset)).wrap_err("get_header_by_hash");
+		assert!(matches!(
+			RpcError::from(error),
+			RpcError::ConnectionFailed(error) if error.starts_with("get_header_by_hash: ")
+		));
+
+		let decoding = Report::new(subxt::Error::Other("Invalid proof".to_string()));
+		assert!(matches!(
+			RpcError::from(decoding),
+			RpcError::RequestFailed(error) if error.contains("Invalid proof")
+		));
+	}
+
+	/// Starts WebSocket server which closes connections right after the handshake
+	fn websocket_server() -> u16 {
+		let route = warp::ws().map(|ws: warp::ws::Ws| ws.on_upgrade(|_| async {}));
+		let (address, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+		tokio::spawn(server);
+		address.port()
+	}
+
+	/// Sends WebSocket handshake request and checks if the server switches protocols
+	async fn websocket_handshake(url: &str) -> Result<()> {
+		let address = url.trim_start_matches("ws://");
+		let mut stream = TcpStream::connect(address).await?;
+		let request = format!(
+			"GET / HTTP/1.1\r\nHost: {address}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+			Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n"
+		);
+		stream.write_all(request.as_bytes()).await?;
+		let mut status = [0; 12];
+		stream.read_exact(&mut status).await?;
+		if status != *b"HTTP/1.1 101" {
+			return Err(eyre!("Unexpected handshake response"));
+		}
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_probe_nodes() {
+		let open_port = websocket_server();
+		// Connection is accepted, but the WebSocket handshake is never answered
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let silent_port = listener.local_addr().unwrap().port();
+		let full_nodes = [
+			format!("ws://127.0.0.1:{silent_port}"),
+			format!("ws://127.0.0.1:{open_port}"),
+		];
+
+		let timeout = Duration::from_millis(500);
+		let reachable = probe_nodes(&full_nodes, timeout, websocket_handshake).await;
+		assert_eq!(reachable, vec![full_nodes[1].clone()]);
+	}
+
+	#[tokio::test]
+	async fn test_reachable_nodes() {
+		// Connection is accepted, but the WebSocket handshake is never answered
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let silent_port = listener.local_addr().unwrap().port();
+		// Port is released when the listener is dropped, so connections are refused
+		let closed_port = {
+			let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+			listener.local_addr().unwrap().port()
+		};
+		let full_nodes = [
+			format!("ws://127.0.0.1:{silent_port}"),
+			format!("ws://127.0.0.1:{closed_port}"),
+		];
+
+		let timeout = Duration::from_millis(200);
+		assert!(reachable_nodes(&full_nodes, timeout).await.is_empty());
+		assert!(reachable_nodes(&[], timeout).await.is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_health_check_nodes_timeout() {
+		// Connection is accepted, but the WebSocket handshake is never answered
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let silent_port = listener.local_addr().unwrap().port();
+		let full_nodes = [format!("ws://127.0.0.1:{silent_port}")];
+
+		let check = health_check_nodes(
+			&full_nodes,
+			&ExpectedNodeVariant::default(),
+			Duration::from_millis(200),
+		);
+		let reports = time::timeout(Duration::from_secs(5), check).await.unwrap();
+		assert_eq!(reports.len(), 1);
+		assert!(!reports[0].reachable);
+		assert!(!reports[0].is_healthy());
+	}
+
+	fn positions(count: u16) -> Vec<Position> {
+		(0..count).map(|col| Position { row: 0, col }).collect()
+	}
+
+	#[test]
+	fn test_cells_with_proofs() {
+		let proofs = vec![(U256::from(1), vec![1; PROOF_SIZE]); 2];
+		let cells = cells_with_proofs(&positions(2), proofs).unwrap();
+		assert_eq!(cells.len(), 2);
+		assert_eq!(cells[1].position, Position { row: 0, col: 1 });
+		assert_eq!(cells[1].content[..PROOF_SIZE], [1; PROOF_SIZE]);
+		assert_eq!(cells[1].content[CELL_WITH_PROOF_SIZE - 1], 1);
+	}
+
+	#[test]
+	fn test_cells_with_truncated_proof() {
+		let mut proofs = vec![(U256::from(1), vec![1; PROOF_SIZE]); 2];
+		proofs[1].1.truncate(PROOF_SIZE - 1);
+		assert!(cells_with_proofs(&positions(2), proofs).is_err());
+	}
+
+	#[test]
+	fn test_cells_with_proofs_error_context() {
+		let proofs = vec![(U256::from(1), vec![1; PROOF_SIZE])];
+		let error = cells_with_proofs(&positions(2), proofs).unwrap_err();
+		assert_eq!(error.to_string(), "cells_with_proofs");
+
+		let mut proofs = vec![(U256::from(1), vec![1; PROOF_SIZE]); 2];
+		proofs[1].1.truncate(PROOF_SIZE - 1);
+		let error = cells_with_proofs(&positions(2), proofs).unwrap_err();
+		assert_eq!(
+			error.to_string(),
+			format!("cells_with_proofs: position {:?}", positions(2)[1])
+		);
+	}
+
+	#[test]
+	fn test_row_columns() {
+		let row = [0, 1, 2, 3, 4];
+		assert_eq!(row_columns(&row, &(1..3)), [1, 2]);
+		assert_eq!(row_columns(&row, &(3..10)), [3, 4]);
+		assert!(row_columns(&row, &(6..10)).is_empty());
+		assert!(row_columns(&row, &(3..1)).is_empty());
+	}
+
+	#[test]
+	fn test_row_bytes() {
+		let bytes = row_bytes(&[U256::from(1), U256::from(2)]);
+		assert_eq!(bytes.len(), 64);
+		assert_eq!(bytes[31], 1);
+		assert_eq!(bytes[63], 2);
+	}
+
+	#[test]
+	fn test_cells_with_missing_proof() {
+		let proofs = vec![(U256::from(1), vec![1; PROOF_SIZE]); 2];
+		assert!(cells_with_proofs(&positions(3), proofs).is_err());
+	}
+}
@@ -1,8 +1,8 @@
 //! Parallelized proof verification
 
+use crate::network::rpc::ConfidenceCalculator;
 use color_eyre::eyre;
 use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
-use itertools::{Either, Itertools};
 use kate_recovery::{
 	data::Cell,
 	matrix::{Dimensions, Position},
@@ -10,54 +10,303 @@ use kate_recovery::{
 };
 use std::sync::Arc;
 use tokio::{task::JoinSet, time::Instant};
-use tracing::debug;
+use tracing::{debug, error, instrument, trace, Span};
 
-async fn verify_proof(
+/// Outcome of the proof verification
+#[derive(Debug, Default, PartialEq)]
+pub struct VerificationSummary {
+	/// Number of cells with valid proofs
+	pub verified: usize,
+	/// Positions of cells with invalid proofs, or proofs which couldn't be verified
+	pub failed: Vec<Position>,
+	/// Positions of cells which verification was cancelled, since the target confidence
+	/// was already reached (see [`verify_until_confident`]). These cells are not verified.
+	pub cancelled: Vec<Position>,
+}
+
+impl VerificationSummary {
+	/// Checks if the cell at the given position is neither failed nor cancelled
+	pub fn is_verified(&self, position: &Position) -> bool {
+		!self.failed.contains(position) && !self.cancelled.contains(position)
+	}
+}
+
+/// Verifies proof of a single cell, blocking the current thread for the duration of verification.
+/// Cells which cannot be verified are considered invalid.
+fn verify_proof(
+	block_num: u32,
 	public_parameters: Arc<PublicParameters>,
 	dimensions: Dimensions,
 	commitment: [u8; 48],
 	cell: Cell,
-) -> Result<(Position, bool), proof::Error> {
-	proof::verify(&public_parameters, dimensions, &commitment, &cell)
-		.map(|verified| (cell.position, verified))
+) -> (Position, bool) {
+	let Position { row, col } = cell.position;
+	match proof::verify(&public_parameters, dimensions, &commitment, &cell) {
+		Ok(verified) => {
+			trace!(block_num, row, col, verified, "Cell verified");
+			(cell.position, verified)
+		},
+		Err(error) => {
+			error!(block_num, row, col, ?error, "Cell verification failed");
+			(cell.position, false)
+		},
+	}
 }
 
-/// Verifies proofs for given block, cells and commitments
+/// Spawns proof verification of each cell on the blocking thread pool.
+/// Returns spawned tasks, and results of cells which cannot be verified.
+fn spawn_verifications(
+	block_num: u32,
+	dimensions: Dimensions,
+	cells: &[Cell],
+	commitments: &[[u8; 48]],
+	public_parameters: Arc<PublicParameters>,
+) -> (JoinSet<(Position, bool)>, Vec<(Position, bool)>) {
+	let mut tasks = JoinSet::new();
+	let mut results = Vec::with_capacity(cells.len());
+
+	for cell in cells {
+		// Cells outside of committed rows cannot be verified
+		let Some(&commitment) = commitments.get(cell.position.row as usize) else {
+			let Position { row, col } = cell.position;
+			error!(
+				block_num,
+				row, col, "Commitment for the cell row is missing"
+			);
+			results.push((cell.position, false));
+			continue;
+		};
+		let public_parameters = public_parameters.clone();
+		let cell = cell.clone();
+		tasks.spawn_blocking(move || {
+			verify_proof(block_num, public_parameters, dimensions, commitment, cell)
+		});
+	}
+	(tasks, results)
+}
+
+fn verification_summary(results: &[(Position, bool)]) -> VerificationSummary {
+	let failed = results
+		.iter()
+		.filter(|(_, is_verified)| !is_verified)
+		.map(|(position, _)| position.clone())
+		.collect::<Vec<_>>();
+	let verified = results.len() - failed.len();
+	VerificationSummary {
+		verified,
+		failed,
+		cancelled: vec![],
+	}
+}
+
+/// Collects results of the verification tasks, until the required number of cells is verified.
+/// Returns number of verified cells.
+async fn join_until_verified(
+	tasks: &mut JoinSet<(Position, bool)>,
+	results: &mut Vec<(Position, bool)>,
+	required: usize,
+) -> eyre::Result<usize> {
+	let mut verified = 0;
+	while verified < required {
+		let Some(result) = tasks.join_next().await else {
+			break;
+		};
+		let (position, is_verified) = result?;
+		verified += usize::from(is_verified);
+		results.push((position, is_verified));
+	}
+	Ok(verified)
+}
+
+/// Returns positions of the cells without the verification result
+fn cancelled_positions(cells: &[Cell], results: &[(Position, bool)]) -> Vec<Position> {
+	cells
+		.iter()
+		.map(|cell| cell.position.clone())
+		.filter(|position| !results.iter().any(|(verified, _)| verified == position))
+		.collect()
+}
+
+/// Verifies proofs for given block, cells and commitments.
+/// Proof verification is CPU bound, so each cell is verified on the blocking thread pool.
+#[instrument(skip_all, fields(block_num = block_num, total_cells = cells.len(), verified_cells), level = "debug")]
 pub async fn verify(
 	block_num: u32,
 	dimensions: Dimensions,
 	cells: &[Cell],
 	commitments: &[[u8; 48]],
 	public_parameters: Arc<PublicParameters>,
-) -> eyre::Result<(Vec<Position>, Vec<Position>)> {
+) -> eyre::Result<VerificationSummary> {
 	if cells.is_empty() {
-		return Ok((Vec::new(), Vec::new()));
+		return Ok(VerificationSummary::default());
 	};
 
 	let start_time = Instant::now();
 
-	let mut tasks = JoinSet::new();
-
-	for cell in cells {
-		tasks.spawn(verify_proof(
-			public_parameters.clone(),
-			dimensions,
-			commitments[cell.position.row as usize],
-			cell.clone(),
-		));
-	}
+	let (mut tasks, mut results) =
+		spawn_verifications(block_num, dimensions, cells, commitments, public_parameters);
 
-	let mut results = Vec::with_capacity(cells.len());
 	while let Some(result) = tasks.join_next().await {
-		results.push(result??)
+		results.push(result?)
 	}
 
 	debug!(block_num, duration = ?start_time.elapsed(), "Proof verification completed");
 
-	Ok(results
-		.into_iter()
-		.partition_map(|(position, is_verified)| match is_verified {
-			true => Either::Left(position),
-			false => Either::Right(position),
-		}))
+	let summary = verification_summary(&results);
+	Span::current().record("verified_cells", summary.verified);
+	Ok(summary)
+}
+
+/// Verifies proofs like [`verify`], but returns as soon as enough cells are verified
+/// to achieve the target confidence, according to the given confidence calculator.
+/// Pending verifications are cancelled, and their positions are returned as cancelled,
+/// so callers must check [`VerificationSummary::is_verified`] instead of the failed cells only.
+///
+/// Early return is useful to library consumers which fetch more cells than needed for
+/// the target confidence. The light client samples exactly the number of cells needed,
+/// so it verifies all of them with [`verify`].
+#[instrument(skip_all, fields(block_num = block_num, total_cells = cells.len(), verified_cells), level = "debug")]
+pub async fn verify_until_confident(
+	block_num: u32,
+	dimensions: Dimensions,
+	cells: &[Cell],
+	commitments: &[[u8; 48]],
+	public_parameters: Arc<PublicParameters>,
+	confidence_calculator: &(dyn ConfidenceCalculator + Send + Sync),
+	target_confidence: f64,
+) -> eyre::Result<VerificationSummary> {
+	let required = confidence_calculator.cells_needed(target_confidence) as usize;
+	let start_time = Instant::now();
+
+	let (mut tasks, mut results) =
+		spawn_verifications(block_num, dimensions, cells, commitments, public_parameters);
+
+	let verified = join_until_verified(&mut tasks, &mut results, required).await?;
+	// Verifications which didn't start yet are cancelled
+	tasks.abort_all();
+
+	debug!(block_num, duration = ?start_time.elapsed(), verified, required, "Proof verification completed");
+
+	let mut summary = verification_summary(&results);
+	summary.cancelled = cancelled_positions(cells, &results);
+	Span::current().record("verified_cells", summary.verified);
+	Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		cancelled_positions, join_until_verified, verification_summary, verify,
+		verify_until_confident, VerificationSummary,
+	};
+	use crate::network::rpc::DefaultConfidenceCalculator;
+	use kate_recovery::{
+		data::Cell,
+		matrix::{Dimensions, Position},
+		testnet,
+	};
+	use std::{future, sync::Arc};
+	use tokio::task::JoinSet;
+
+	#[tokio::test]
+	async fn test_verify_without_cells() {
+		let public_parameters = Arc::new(testnet::public_params(1024));
+		let dimensions = Dimensions::new(1, 4).unwrap();
+		let summary = verify(1, dimensions, &[], &[], public_parameters)
+			.await
+			.unwrap();
+		assert_eq!(summary, VerificationSummary::default());
+	}
+
+	#[tokio::test]
+	async fn test_verify_corrupt_cell() {
+		let public_parameters = Arc::new(testnet::public_params(1024));
+		let dimensions = Dimensions::new(1, 4).unwrap();
+		let position = Position { row: 0, col: 1 };
+		let corrupt = Cell {
+			position,
+			content: [0xff; 80],
+		};
+		let summary = verify(1, dimensions, &[corrupt], &[[0; 48]], public_parameters)
+			.await
+			.unwrap();
+		assert_eq!(summary.verified, 0);
+		assert_eq!(summary.failed, vec![position]);
+	}
+
+	#[tokio::test]
+	async fn test_verify_missing_commitment() {
+		let public_parameters = Arc::new(testnet::public_params(1024));
+		let dimensions = Dimensions::new(1, 4).unwrap();
+		let position = Position { row: 1, col: 0 };
+		let cell = Cell {
+			position,
+			content: [0; 80],
+		};
+		let summary = verify(1, dimensions, &[cell], &[[0; 48]], public_parameters)
+			.await
+			.unwrap();
+		assert_eq!(summary.verified, 0);
+		assert_eq!(summary.failed, vec![position]);
+	}
+
+	#[tokio::test]
+	async fn test_verify_until_confident_not_reached() {
+		let public_parameters = Arc::new(testnet::public_params(1024));
+		let dimensions = Dimensions::new(1, 4).unwrap();
+		let cells = (0..4)
+			.map(|col| Cell {
+				position: Position { row: 0, col },
+				content: [0xff; 80],
+			})
+			.collect::<Vec<_>>();
+		// Target confidence is never reached, so all cells are verified
+		let summary = verify_until_confident(
+			1,
+			dimensions,
+			&cells,
+			&[[0; 48]],
+			public_parameters,
+			&DefaultConfidenceCalculator,
+			99.0,
+		)
+		.await
+		.unwrap();
+		assert_eq!(summary.verified, 0);
+		assert_eq!(summary.failed.len(), 4);
+		assert!(summary.cancelled.is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_join_until_verified() {
+		let cells = (0..3)
+			.map(|col| Cell {
+				position: Position { row: 0, col },
+				content: [0; 80],
+			})
+			.collect::<Vec<_>>();
+		let mut tasks = JoinSet::new();
+		tasks.spawn(async { (Position { row: 0, col: 0 }, true) });
+		tasks.spawn(async { (Position { row: 0, col: 1 }, true) });
+		// Verification which never completes doesn't block once the target is reached
+		tasks.spawn(async {
+			future::pending::<()>().await;
+			(Position { row: 0, col: 2 }, true)
+		});
+
+		let mut results = vec![];
+		let verified = join_until_verified(&mut tasks, &mut results, 2)
+			.await
+			.unwrap();
+		tasks.abort_all();
+		assert_eq!(verified, 2);
+
+		let mut summary = verification_summary(&results);
+		summary.cancelled = cancelled_positions(&cells, &results);
+		assert_eq!(summary.verified, 2);
+		assert!(summary.failed.is_empty());
+		assert_eq!(summary.cancelled, vec![Position { row: 0, col: 2 }]);
+		assert!(summary.is_verified(&Position { row: 0, col: 0 }));
+		assert!(!summary.is_verified(&Position { row: 0, col: 2 }));
+	}
 }
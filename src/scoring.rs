@@ -0,0 +1,105 @@
+//! Composite data availability score of the block
+
+/// Default weight of the sampling confidence in the data availability score,
+/// the remaining weight is given to the app data coverage
+pub const DEFAULT_CONFIDENCE_WEIGHT: f64 = 0.7;
+
+/// Ratio of two counts in range [0, 1], zero if there is nothing to count
+fn ratio(part: u32, total: u32) -> f64 {
+	if total == 0 {
+		return 0.0;
+	}
+	(part.min(total) as f64) / (total as f64)
+}
+
+/// Calculates data availability score using [`DEFAULT_CONFIDENCE_WEIGHT`]
+/// (see [`weighted_data_availability_score`]).
+pub fn data_availability_score(
+	verified: u32,
+	sampled: u32,
+	app_cells_verified: u32,
+	app_cells_total: u32,
+) -> f64 {
+	weighted_data_availability_score(
+		verified,
+		sampled,
+		app_cells_verified,
+		app_cells_total,
+		DEFAULT_CONFIDENCE_WEIGHT,
+	)
+}
+
+/// Calculates data availability score in range [0, 1], combining the ratio of verified
+/// sampled cells with the ratio of verified app data cells.
+/// Confidence weight is clamped to [0, 1], and the app coverage gets the remaining weight.
+/// If block has no app data cells, score is equal to the ratio of verified sampled cells.
+pub fn weighted_data_availability_score(
+	verified: u32,
+	sampled: u32,
+	app_cells_verified: u32,
+	app_cells_total: u32,
+	confidence_weight: f64,
+) -> f64 {
+	let confidence = ratio(verified, sampled);
+	if app_cells_total == 0 {
+		return confidence;
+	}
+	let coverage = ratio(app_cells_verified, app_cells_total);
+	let weight = if confidence_weight.is_nan() {
+		DEFAULT_CONFIDENCE_WEIGHT
+	} else {
+		confidence_weight.clamp(0.0, 1.0)
+	};
+	weight * confidence + (1.0 - weight) * coverage
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use proptest::prelude::*;
+	use test_case::test_case;
+
+	#[test_case(8, 8, 4, 4 => 1.0 ; "all cells verified")]
+	#[test_case(8, 8, 0, 0 => 1.0 ; "zero app cells")]
+	#[test_case(4, 8, 0, 0 => 0.5 ; "zero app cells with partial verification")]
+	#[test_case(0, 0, 0, 0 => 0.0 ; "nothing sampled")]
+	#[test_case(0, 8, 0, 4 => 0.0 ; "nothing verified")]
+	#[test_case(8, 8, 0, 4 => 0.7 ; "app cells not verified")]
+	#[test_case(4, 8, 2, 4 => 0.5 ; "partial verification")]
+	fn test_data_availability_score(
+		verified: u32,
+		sampled: u32,
+		app_cells_verified: u32,
+		app_cells_total: u32,
+	) -> f64 {
+		data_availability_score(verified, sampled, app_cells_verified, app_cells_total)
+	}
+
+	#[test_case(1.0 => 1.0 ; "confidence only")]
+	#[test_case(0.0 => 0.25 ; "coverage only")]
+	#[test_case(2.0 => 1.0 ; "weight above range")]
+	#[test_case(-1.0 => 0.25 ; "weight below range")]
+	fn test_confidence_weight(weight: f64) -> f64 {
+		weighted_data_availability_score(8, 8, 1, 4, weight)
+	}
+
+	proptest! {
+		#[test]
+		fn score_is_in_range(
+			verified in any::<u32>(),
+			sampled in any::<u32>(),
+			app_cells_verified in any::<u32>(),
+			app_cells_total in any::<u32>(),
+			weight in any::<f64>()
+		) {
+			let score = weighted_data_availability_score(
+				verified,
+				sampled,
+				app_cells_verified,
+				app_cells_total,
+				weight,
+			);
+			prop_assert!((0.0..=1.0).contains(&score));
+		}
+	}
+}
@@ -135,7 +135,7 @@ async fn process_block(
 			let commitments = commitments::from_slice(&commitment)?;
 
 			// now this is in `u64`
-			let cell_count = rpc::cell_count_for_confidence(cfg.confidence);
+			let cell_count = cfg.confidence_calculator.cells_needed(cfg.confidence);
 			let positions = rpc::generate_random_cells(dimensions, cell_count);
 
 			let (fetched, unfetched, _fetch_stats) = network_client
@@ -254,7 +254,7 @@ mod tests {
 	use std::time::Duration;
 
 	use super::*;
-	use crate::types::{self, RuntimeConfig};
+	use crate::types::{self, CellFetchSource, RuntimeConfig};
 	use avail_subxt::{
 		api::runtime_types::avail_core::{
 			data_lookup::compact::CompactDataLookup,
@@ -370,6 +370,7 @@ mod tests {
 					fetched.len(),
 					Duration::from_secs(0),
 					None,
+					CellFetchSource::DHT,
 				);
 				Box::pin(async move { Ok((fetched, unfetched, stats)) })
 			});
@@ -458,6 +459,7 @@ mod tests {
 					dht_fetched.len(),
 					Duration::from_secs(0),
 					Some((rpc_fetched.len(), Duration::from_secs(1))),
+					CellFetchSource::DHT,
 				);
 				let fetched = [&dht_fetched[..], &rpc_fetched[..]].concat();
 				Box::pin(async move { Ok((fetched, unfetched, stats)) })
@@ -478,4 +480,62 @@ mod tests {
 		.await
 		.unwrap();
 	}
+
+	#[derive(Debug)]
+	struct FixedCellCount(u32);
+
+	impl rpc::ConfidenceCalculator for FixedCellCount {
+		fn cells_needed(&self, _confidence: f64) -> u32 {
+			self.0
+		}
+	}
+
+	#[tokio::test]
+	pub async fn test_process_block_with_custom_confidence_calculator() {
+		let (block_tx, _) = broadcast::channel::<types::BlockVerified>(10);
+		let mut cfg = SyncClientConfig::from(&RuntimeConfig::default());
+		cfg.confidence_calculator = Arc::new(FixedCellCount(2));
+		let mut mock_network_client = network::MockClient::new();
+		let mut mock_client = MockClient::new();
+		let header_hash: H256 =
+			hex!("3767f8955d6f7306b1e55701b6316fa1163daa8d4cffdb05c3b25db5f5da1723").into();
+
+		mock_network_client
+			.expect_fetch_verified()
+			.withf(|_, _, _, _, positions| positions.len() == 2)
+			.times(1)
+			.returning(move |_, _, _, _, positions| {
+				let fetched = positions
+					.iter()
+					.map(|position| Cell {
+						position: position.clone(),
+						content: [0; 80],
+					})
+					.collect::<Vec<_>>();
+				let stats = network::FetchStats::new(
+					positions.len(),
+					fetched.len(),
+					Duration::from_secs(0),
+					None,
+					CellFetchSource::DHT,
+				);
+				Box::pin(async move { Ok((fetched, vec![], stats)) })
+			});
+		mock_client
+			.expect_store_confidence()
+			.with(eq(2), eq(2))
+			.times(1)
+			.returning(|_, _| Ok(()));
+
+		process_block(
+			&mock_client,
+			&mock_network_client,
+			default_header(),
+			header_hash,
+			&cfg,
+			block_tx,
+		)
+		.await
+		.unwrap();
+	}
 }
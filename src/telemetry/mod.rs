@@ -1,11 +1,51 @@
-use crate::types::Origin;
+use crate::types::{CellFetchSource, Origin};
 use async_trait::async_trait;
 use color_eyre::Result;
 use mockall::automock;
+use std::{borrow::Cow, fmt, future::Future, sync::Arc, time::Duration};
+use tracing::warn;
 
+#[cfg(test)]
+pub mod noop;
 pub mod otlp;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
 
+/// Histogram bucket boundaries of duration metrics, in seconds
+const DURATION_BOUNDARIES: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 60.0];
+
+/// Errors which can occur while counting or recording metrics.
 #[derive(Debug)]
+pub enum MetricError {
+	/// Metric buffer lock is poisoned
+	LockPoisoned,
+	/// Instrument for the metric is missing or cannot be created
+	InstrumentError(String),
+	/// Custom metric uses name reserved for built-in metrics
+	ReservedName(String),
+}
+
+impl fmt::Display for MetricError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MetricError::LockPoisoned => write!(f, "Metrics buffer lock is poisoned"),
+			MetricError::InstrumentError(error) => write!(f, "Metrics instrument error: {error}"),
+			MetricError::ReservedName(name) => write!(f, "Metric name {name} is reserved"),
+		}
+	}
+}
+
+impl std::error::Error for MetricError {}
+
+/// Checks if the name is used by a built-in metric, so it cannot be used by custom metrics
+fn is_reserved_name(name: &str) -> bool {
+	MetricCounter::iter().any(|counter| counter.name() == name)
+		|| MetricValue::BUILTIN
+			.iter()
+			.any(|value| value.name() == name)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MetricCounter {
 	Starts,
 	SessionBlocks,
@@ -38,6 +78,22 @@ impl MetricName for MetricCounter {
 }
 
 impl MetricCounter {
+	const ALL: [MetricCounter; 8] = [
+		MetricCounter::Starts,
+		MetricCounter::SessionBlocks,
+		MetricCounter::OutgoingConnectionErrors,
+		MetricCounter::IncomingConnectionErrors,
+		MetricCounter::IncomingConnections,
+		MetricCounter::EstablishedConnections,
+		MetricCounter::IncomingPutRecord,
+		MetricCounter::IncomingGetRecord,
+	];
+
+	/// Iterates over all counter variants
+	pub fn iter() -> impl Iterator<Item = MetricCounter> {
+		Self::ALL.into_iter()
+	}
+
 	fn is_buffered(&self) -> bool {
 		!matches!(self, MetricCounter::Starts)
 	}
@@ -62,19 +118,46 @@ pub enum MetricValue {
 
 	DHTFetched(f64),
 	DHTFetchedPercentage(f64),
-	DHTFetchDuration(f64),
-	DHTPutDuration(f64),
+	DHTFetchDuration(Duration),
+	DHTPutDuration(Duration),
 	DHTPutSuccess(f64),
 
 	DHTConnectedPeers(usize),
+	/// Set if the number of DHT peers is below the configured threshold
+	PeerCountBelowThreshold(bool),
+	/// Number of discovered peers per second
+	PeerDiscoveryRate(f64),
+	/// Number of established connections per second
+	PeerConnectionRate(f64),
+	/// Number of closed connections per second
+	PeerChurnRate(f64),
 	DHTQueryTimeout(u32),
 	DHTPingLatency(f64),
+	BootstrapConnected(usize),
 
 	RPCFetched(f64),
-	RPCFetchDuration(f64),
-	RPCCallDuration(f64),
+	RPCFetchDuration(Duration),
+	RPCCallDuration(Duration),
+	NodeConnectionAttempts(u32),
+	NodeConnectionRetries(u32),
+	RPCHeaderCacheHitRate(f64),
+	/// Number of RPC nodes with open circuit breaker
+	RPCOpenCircuits(usize),
+	CellFetchSource(CellFetchSource),
 
 	Up(),
+	/// Latest block for which the full maintenance was performed
+	MaintenanceLastRun(u32),
+
+	CustomHistogram(&'static str, f64, Vec<f64>),
+	CustomGaugeF64 {
+		name: String,
+		value: f64,
+	},
+	CustomGaugeU64 {
+		name: String,
+		value: u64,
+	},
 
 	#[cfg(feature = "crawl")]
 	CrawlCellsSuccessRate(f64),
@@ -84,11 +167,50 @@ pub enum MetricValue {
 	CrawlBlockDelay(f64),
 }
 
-impl MetricName for MetricValue {
-	fn name(&self) -> &'static str {
+impl MetricValue {
+	/// Value of every built-in metric, used to reserve their names
+	const BUILTIN: &'static [MetricValue] = &[
+		MetricValue::BlockHeight(0),
+		MetricValue::BlockConfidence(0.0),
+		MetricValue::BlockConfidenceThreshold(0.0),
+		MetricValue::BlockProcessingDelay(0.0),
+		MetricValue::DHTReplicationFactor(0),
+		MetricValue::DHTFetched(0.0),
+		MetricValue::DHTFetchedPercentage(0.0),
+		MetricValue::DHTFetchDuration(Duration::ZERO),
+		MetricValue::DHTPutDuration(Duration::ZERO),
+		MetricValue::DHTPutSuccess(0.0),
+		MetricValue::DHTConnectedPeers(0),
+		MetricValue::PeerCountBelowThreshold(false),
+		MetricValue::PeerDiscoveryRate(0.0),
+		MetricValue::PeerConnectionRate(0.0),
+		MetricValue::PeerChurnRate(0.0),
+		MetricValue::DHTQueryTimeout(0),
+		MetricValue::DHTPingLatency(0.0),
+		MetricValue::BootstrapConnected(0),
+		MetricValue::RPCFetched(0.0),
+		MetricValue::RPCFetchDuration(Duration::ZERO),
+		MetricValue::RPCCallDuration(Duration::ZERO),
+		MetricValue::NodeConnectionAttempts(0),
+		MetricValue::NodeConnectionRetries(0),
+		MetricValue::RPCHeaderCacheHitRate(0.0),
+		MetricValue::RPCOpenCircuits(0),
+		MetricValue::CellFetchSource(CellFetchSource::DHT),
+		MetricValue::Up(),
+		MetricValue::MaintenanceLastRun(0),
+		#[cfg(feature = "crawl")]
+		MetricValue::CrawlCellsSuccessRate(0.0),
+		#[cfg(feature = "crawl")]
+		MetricValue::CrawlRowsSuccessRate(0.0),
+		#[cfg(feature = "crawl")]
+		MetricValue::CrawlBlockDelay(0.0),
+	];
+
+	/// Returns name of the metric, names of built-in metrics are borrowed
+	pub fn name(&self) -> Cow<'static, str> {
 		use MetricValue::*;
 
-		match self {
+		let name = match self {
 			BlockHeight(_) => "avail.light.block.height",
 			BlockConfidence(_) => "avail.light.block.confidence",
 			BlockConfidenceThreshold(_) => "avail.light.block.confidence_threshold",
@@ -102,14 +224,30 @@ impl MetricName for MetricValue {
 			DHTPutSuccess(_) => "avail.light.dht.put_success",
 
 			DHTConnectedPeers(_) => "avail.light.dht.connected_peers",
+			PeerCountBelowThreshold(_) => "avail.light.dht.peer_count_below_threshold",
+			PeerDiscoveryRate(_) => "avail.light.dht.discovery_rate",
+			PeerConnectionRate(_) => "avail.light.dht.connection_rate",
+			PeerChurnRate(_) => "avail.light.dht.churn_rate",
 			DHTQueryTimeout(_) => "avail.light.dht.query_timeout",
 			DHTPingLatency(_) => "avail.light.dht.ping_latency",
+			BootstrapConnected(_) => "avail.light.dht.bootstrap_connected",
 
 			RPCFetched(_) => "avail.light.rpc.fetched",
 			RPCFetchDuration(_) => "avail.light.rpc.fetch_duration",
 			RPCCallDuration(_) => "avail.light.rpc.call_duration",
+			NodeConnectionAttempts(_) => "avail.light.rpc.node_connection_attempts",
+			NodeConnectionRetries(_) => "avail.light.rpc.node_connection_retries",
+			RPCHeaderCacheHitRate(_) => "avail.light.rpc.header_cache_hit_rate",
+			RPCOpenCircuits(_) => "avail.light.rpc.open_circuits",
+			CellFetchSource(_) => "avail.light.cell_fetch_source",
 
 			Up() => "avail.light.up",
+			MaintenanceLastRun(_) => "avail.light.maintenance.last_run",
+
+			CustomHistogram(name, _, _) => *name,
+			CustomGaugeF64 { name, .. } | CustomGaugeU64 { name, .. } => {
+				return Cow::Owned(name.clone())
+			},
 
 			#[cfg(feature = "crawl")]
 			CrawlCellsSuccessRate(_) => "avail.light.crawl.cells_success_rate",
@@ -117,11 +255,34 @@ impl MetricName for MetricValue {
 			CrawlRowsSuccessRate(_) => "avail.light.crawl.rows_success_rate",
 			#[cfg(feature = "crawl")]
 			CrawlBlockDelay(_) => "avail.light.crawl.block_delay",
+		};
+		Cow::Borrowed(name)
+	}
+
+	/// Creates histogram metric with custom bucket boundaries
+	pub fn custom_histogram(name: &'static str, value: f64, boundaries: Vec<f64>) -> Self {
+		MetricValue::CustomHistogram(name, value, boundaries)
+	}
+
+	/// Checks that custom metric doesn't use name of a built-in metric
+	fn validate_name(&self) -> Result<(), MetricError> {
+		use MetricValue::*;
+		match self {
+			CustomHistogram(..) | CustomGaugeF64 { .. } | CustomGaugeU64 { .. }
+				if is_reserved_name(&self.name()) =>
+			{
+				Err(MetricError::ReservedName(self.name().into_owned()))
+			},
+			_ => Ok(()),
 		}
 	}
-}
 
-impl MetricValue {
+	/// Checks if the metric is recorded for the given origin.
+	/// Can be used to skip computing values which would be dropped anyway.
+	pub fn is_allowed_for_origin(&self, origin: &Origin) -> bool {
+		self.is_allowed(origin)
+	}
+
 	// Metric filter for external peers
 	// Only the metrics we wish to send to OTel should be in this list
 	fn is_allowed(&self, origin: &Origin) -> bool {
@@ -140,7 +301,303 @@ impl MetricValue {
 #[automock]
 #[async_trait]
 pub trait Metrics {
-	async fn count(&self, counter: MetricCounter);
-	async fn record(&self, value: MetricValue);
+	async fn count(&self, counter: MetricCounter) -> Result<(), MetricError>;
+	async fn record(&self, value: MetricValue) -> Result<(), MetricError>;
+	/// Records custom duration metric in seconds, using the default duration buckets
+	async fn record_duration(
+		&self,
+		name: &'static str,
+		duration: Duration,
+	) -> Result<(), MetricError> {
+		let value = duration.as_secs_f64();
+		let boundaries = DURATION_BOUNDARIES.to_vec();
+		self.record(MetricValue::custom_histogram(name, value, boundaries))
+			.await
+	}
 	async fn flush(&self) -> Result<()>;
+	/// Flushes buffered metrics and waits until all pending exports to the collector complete
+	async fn flush_all(&self) -> Result<()>;
+}
+
+/// Updates metrics, logging errors instead of returning them.
+/// Failure to update a metric shouldn't interrupt the caller.
+pub trait MetricsExt: Metrics {
+	fn count_or_warn(&self, counter: MetricCounter) -> impl Future<Output = ()> {
+		async move {
+			if let Err(error) = self.count(counter).await {
+				warn!("Cannot count metric: {error}");
+			}
+		}
+	}
+
+	fn record_or_warn(&self, value: MetricValue) -> impl Future<Output = ()> {
+		async move {
+			if let Err(error) = self.record(value).await {
+				warn!("Cannot record metric: {error}");
+			}
+		}
+	}
+}
+
+impl<T: Metrics + ?Sized> MetricsExt for T {}
+
+/// Metrics which forwards every update to both backends
+pub struct CombinedMetrics<A, B> {
+	first: Arc<A>,
+	second: Arc<B>,
+}
+
+impl<A, B> CombinedMetrics<A, B> {
+	pub fn new(first: Arc<A>, second: Arc<B>) -> Self {
+		CombinedMetrics { first, second }
+	}
+}
+
+#[async_trait]
+impl<A, B> Metrics for CombinedMetrics<A, B>
+where
+	A: Metrics + Send + Sync,
+	B: Metrics + Send + Sync,
+{
+	/// Updates both backends, returning the first error, if any
+	async fn count(&self, counter: MetricCounter) -> Result<(), MetricError> {
+		let first = self.first.count(counter).await;
+		let second = self.second.count(counter).await;
+		first.and(second)
+	}
+
+	async fn record(&self, value: MetricValue) -> Result<(), MetricError> {
+		let first = self.first.record(value.clone()).await;
+		let second = self.second.record(value).await;
+		first.and(second)
+	}
+
+	async fn flush(&self) -> Result<()> {
+		let first = self.first.flush().await;
+		let second = self.second.flush().await;
+		first.and(second)
+	}
+
+	async fn flush_all(&self) -> Result<()> {
+		let first = self.first.flush_all().await;
+		let second = self.second.flush_all().await;
+		first.and(second)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use test_case::test_case;
+
+	#[test]
+	fn counters_iter_contains_all_variants() {
+		// Exhaustive match fails to compile when a new variant is added,
+		// reminding to extend `MetricCounter::ALL`
+		let index = |counter: &MetricCounter| match counter {
+			MetricCounter::Starts => 0,
+			MetricCounter::SessionBlocks => 1,
+			MetricCounter::OutgoingConnectionErrors => 2,
+			MetricCounter::IncomingConnectionErrors => 3,
+			MetricCounter::IncomingConnections => 4,
+			MetricCounter::EstablishedConnections => 5,
+			MetricCounter::IncomingPutRecord => 6,
+			MetricCounter::IncomingGetRecord => 7,
+		};
+		let indices = MetricCounter::iter()
+			.map(|counter| index(&counter))
+			.collect::<Vec<_>>();
+		assert_eq!(indices, (0..8).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn builtin_values_contain_all_variants() {
+		// Exhaustive match fails to compile when a new variant is added,
+		// reminding to extend `MetricValue::BUILTIN`
+		use MetricValue::*;
+		let index = |value: &MetricValue| match value {
+			BlockHeight(_) => Some(0),
+			BlockConfidence(_) => Some(1),
+			BlockConfidenceThreshold(_) => Some(2),
+			BlockProcessingDelay(_) => Some(3),
+			DHTReplicationFactor(_) => Some(4),
+			DHTFetched(_) => Some(5),
+			DHTFetchedPercentage(_) => Some(6),
+			DHTFetchDuration(_) => Some(7),
+			DHTPutDuration(_) => Some(8),
+			DHTPutSuccess(_) => Some(9),
+			DHTConnectedPeers(_) => Some(10),
+			PeerCountBelowThreshold(_) => Some(11),
+			PeerDiscoveryRate(_) => Some(12),
+			PeerConnectionRate(_) => Some(13),
+			PeerChurnRate(_) => Some(14),
+			DHTQueryTimeout(_) => Some(15),
+			DHTPingLatency(_) => Some(16),
+			BootstrapConnected(_) => Some(17),
+			RPCFetched(_) => Some(18),
+			RPCFetchDuration(_) => Some(19),
+			RPCCallDuration(_) => Some(20),
+			NodeConnectionAttempts(_) => Some(21),
+			NodeConnectionRetries(_) => Some(22),
+			RPCHeaderCacheHitRate(_) => Some(23),
+			RPCOpenCircuits(_) => Some(24),
+			CellFetchSource(_) => Some(25),
+			Up() => Some(26),
+			MaintenanceLastRun(_) => Some(27),
+			CustomHistogram(..) | CustomGaugeF64 { .. } | CustomGaugeU64 { .. } => None,
+			#[cfg(feature = "crawl")]
+			CrawlCellsSuccessRate(_) => Some(28),
+			#[cfg(feature = "crawl")]
+			CrawlRowsSuccessRate(_) => Some(29),
+			#[cfg(feature = "crawl")]
+			CrawlBlockDelay(_) => Some(30),
+		};
+		let builtin_count = if cfg!(feature = "crawl") { 31 } else { 28 };
+		let indices = MetricValue::BUILTIN.iter().map(index).collect::<Vec<_>>();
+		assert_eq!(indices, (0..builtin_count).map(Some).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn built_in_names_are_reserved() {
+		let names = MetricCounter::iter()
+			.map(|counter| counter.name().to_string())
+			.chain(MetricValue::BUILTIN.iter().map(|value| value.name().into()));
+		for name in names {
+			let value = MetricValue::CustomGaugeU64 {
+				name: name.clone(),
+				value: 1,
+			};
+			assert!(
+				matches!(value.validate_name(), Err(MetricError::ReservedName(reserved)) if reserved == name)
+			);
+		}
+	}
+
+	#[test_case(MetricValue::CustomGaugeF64 { name: "custom".to_string(), value: 1.0 } => true ; "custom f64 gauge")]
+	#[test_case(MetricValue::CustomGaugeU64 { name: "custom".to_string(), value: 1 } => true ; "custom u64 gauge")]
+	#[test_case(MetricValue::CustomGaugeF64 { name: "avail.light.up".to_string(), value: 1.0 } => false ; "reserved f64 gauge")]
+	#[test_case(MetricValue::CustomGaugeU64 { name: "avail.light.block.height".to_string(), value: 1 } => false ; "reserved u64 gauge")]
+	#[test_case(MetricValue::custom_histogram("avail.light.dht.fetch_duration", 1.0, vec![]) => false ; "reserved histogram")]
+	#[test_case(MetricValue::BlockHeight(1) => true ; "built-in metric")]
+	fn test_validate_name(value: MetricValue) -> bool {
+		value.validate_name().is_ok()
+	}
+
+	#[test_case(MetricValue::BlockHeight(1) => false ; "block height")]
+	#[test_case(MetricValue::BlockConfidence(99.0) => true ; "block confidence")]
+	#[test_case(MetricValue::BlockConfidenceThreshold(99.0) => false ; "block confidence threshold")]
+	#[test_case(MetricValue::BlockProcessingDelay(1.0) => false ; "block processing delay")]
+	#[test_case(MetricValue::DHTReplicationFactor(5) => false ; "dht replication factor")]
+	#[test_case(MetricValue::DHTFetched(1.0) => false ; "dht fetched")]
+	#[test_case(MetricValue::DHTFetchedPercentage(1.0) => true ; "dht fetched percentage")]
+	#[test_case(MetricValue::DHTFetchDuration(Duration::from_secs(1)) => false ; "dht fetch duration")]
+	#[test_case(MetricValue::DHTPutDuration(Duration::from_secs(1)) => false ; "dht put duration")]
+	#[test_case(MetricValue::DHTPutSuccess(1.0) => false ; "dht put success")]
+	#[test_case(MetricValue::DHTConnectedPeers(1) => false ; "dht connected peers")]
+	#[test_case(MetricValue::PeerCountBelowThreshold(true) => false ; "peer count below threshold")]
+	#[test_case(MetricValue::PeerDiscoveryRate(1.0) => false ; "peer discovery rate")]
+	#[test_case(MetricValue::PeerConnectionRate(1.0) => false ; "peer connection rate")]
+	#[test_case(MetricValue::PeerChurnRate(1.0) => false ; "peer churn rate")]
+	#[test_case(MetricValue::DHTQueryTimeout(1) => false ; "dht query timeout")]
+	#[test_case(MetricValue::DHTPingLatency(1.0) => false ; "dht ping latency")]
+	#[test_case(MetricValue::BootstrapConnected(1) => false ; "bootstrap connected")]
+	#[test_case(MetricValue::RPCFetched(1.0) => false ; "rpc fetched")]
+	#[test_case(MetricValue::RPCFetchDuration(Duration::from_secs(1)) => false ; "rpc fetch duration")]
+	#[test_case(MetricValue::RPCCallDuration(Duration::from_secs(1)) => false ; "rpc call duration")]
+	#[test_case(MetricValue::NodeConnectionAttempts(1) => false ; "node connection attempts")]
+	#[test_case(MetricValue::NodeConnectionRetries(1) => false ; "node connection retries")]
+	#[test_case(MetricValue::RPCHeaderCacheHitRate(1.0) => false ; "rpc header cache hit rate")]
+	#[test_case(MetricValue::RPCOpenCircuits(1) => false ; "rpc open circuits")]
+	#[test_case(MetricValue::CellFetchSource(CellFetchSource::DHT) => false ; "cell fetch source")]
+	#[test_case(MetricValue::Up() => true ; "up")]
+	#[test_case(MetricValue::MaintenanceLastRun(1) => false ; "maintenance last run")]
+	#[test_case(MetricValue::custom_histogram("custom", 1.0, vec![]) => false ; "custom histogram")]
+	#[test_case(MetricValue::CustomGaugeF64 { name: "custom".to_string(), value: 1.0 } => false ; "custom f64 gauge")]
+	#[test_case(MetricValue::CustomGaugeU64 { name: "custom".to_string(), value: 1 } => false ; "custom u64 gauge")]
+	fn test_is_allowed_for_external_origin(value: MetricValue) -> bool {
+		// All metrics are allowed for other origins
+		let origins = [
+			Origin::Internal,
+			Origin::FatClient,
+			Origin::Other("other".to_string()),
+		];
+		for origin in origins {
+			assert!(value.is_allowed_for_origin(&origin));
+		}
+		value.is_allowed_for_origin(&Origin::External)
+	}
+
+	#[cfg(feature = "crawl")]
+	#[test_case(MetricValue::CrawlCellsSuccessRate(1.0) ; "crawl cells success rate")]
+	#[test_case(MetricValue::CrawlRowsSuccessRate(1.0) ; "crawl rows success rate")]
+	#[test_case(MetricValue::CrawlBlockDelay(1.0) ; "crawl block delay")]
+	fn test_crawl_metrics_are_internal(value: MetricValue) {
+		assert!(value.is_allowed_for_origin(&Origin::Internal));
+		assert!(!value.is_allowed_for_origin(&Origin::External));
+	}
+
+	#[tokio::test]
+	async fn test_combined_metrics() {
+		let mut first = MockMetrics::new();
+		first.expect_count().times(1).returning(|_| Ok(()));
+		first.expect_record().times(1).returning(|_| Ok(()));
+		let mut second = MockMetrics::new();
+		second.expect_count().times(1).returning(|_| Ok(()));
+		second
+			.expect_record()
+			.times(1)
+			.returning(|_| Err(MetricError::LockPoisoned));
+
+		let metrics = CombinedMetrics::new(Arc::new(first), Arc::new(second));
+		assert!(metrics.count(MetricCounter::Starts).await.is_ok());
+		// Failure of one backend doesn't prevent update of the other
+		assert!(matches!(
+			metrics.record(MetricValue::Up()).await,
+			Err(MetricError::LockPoisoned)
+		));
+	}
+
+	#[tokio::test]
+	async fn test_metrics_ext_ignores_errors() {
+		let mut metrics = MockMetrics::new();
+		metrics
+			.expect_count()
+			.times(1)
+			.returning(|_| Err(MetricError::LockPoisoned));
+		metrics
+			.expect_record()
+			.times(1)
+			.returning(|_| Err(MetricError::InstrumentError("error".to_string())));
+
+		metrics.count_or_warn(MetricCounter::Starts).await;
+		metrics.record_or_warn(MetricValue::Up()).await;
+	}
+
+	#[tokio::test]
+	async fn test_record_duration() {
+		let is_expected = |value: &MetricValue| match value {
+			MetricValue::CustomHistogram("custom.duration", seconds, _) => *seconds == 1.5,
+			_ => false,
+		};
+		let mut first = MockMetrics::new();
+		first
+			.expect_record()
+			.withf(is_expected)
+			.times(1)
+			.returning(|_| Ok(()));
+		let mut second = MockMetrics::new();
+		second
+			.expect_record()
+			.withf(is_expected)
+			.times(1)
+			.returning(|_| Ok(()));
+
+		// Combined metrics use the default implementation
+		let metrics = CombinedMetrics::new(Arc::new(first), Arc::new(second));
+		let duration = Duration::from_millis(1500);
+		assert!(metrics
+			.record_duration("custom.duration", duration)
+			.await
+			.is_ok());
+	}
 }
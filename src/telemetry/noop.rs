@@ -0,0 +1,26 @@
+use super::{MetricCounter, MetricError, MetricValue};
+use async_trait::async_trait;
+use color_eyre::Result;
+
+/// Metrics which discard all values, for tests which don't check recorded metrics
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Metrics;
+
+#[async_trait]
+impl super::Metrics for Metrics {
+	async fn count(&self, _: MetricCounter) -> Result<(), MetricError> {
+		Ok(())
+	}
+
+	async fn record(&self, _: MetricValue) -> Result<(), MetricError> {
+		Ok(())
+	}
+
+	async fn flush(&self) -> Result<()> {
+		Ok(())
+	}
+
+	async fn flush_all(&self) -> Result<()> {
+		Ok(())
+	}
+}
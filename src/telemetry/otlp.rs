@@ -1,17 +1,31 @@
-use super::{MetricCounter, MetricValue};
+use super::{MetricCounter, MetricError, MetricValue, DURATION_BOUNDARIES};
 use crate::{
 	telemetry::MetricName,
-	types::{Origin, OtelConfig},
+	types::{block_matrix_partition_format, Origin, OtelConfig},
 };
 use async_trait::async_trait;
-use color_eyre::Result;
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use libp2p::PeerId;
 use opentelemetry_api::{
 	global,
-	metrics::{Counter, Meter},
-	KeyValue,
+	metrics::{Counter, Histogram, Meter},
+	Context, KeyValue,
+};
+use opentelemetry_otlp::{ExportConfig, MetricsExporterBuilder, Protocol, WithExportConfig};
+use opentelemetry_sdk::metrics::{
+	reader::{DefaultAggregationSelector, DefaultTemporalitySelector},
+	Aggregation, Instrument, MeterProvider as SdkMeterProvider, PeriodicReader, Stream, View,
+};
+use std::{
+	borrow::Cow,
+	collections::HashMap,
+	str::FromStr,
+	sync::{Arc, RwLock},
+	time::Duration,
 };
-use opentelemetry_otlp::{ExportConfig, Protocol, WithExportConfig};
-use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::sync::Mutex;
 
 const ATTRIBUTE_NUMBER: usize = 8;
@@ -20,8 +34,11 @@ const ATTRIBUTE_NUMBER: usize = 8;
 // That can be optimized by using dedicated data structure with proper bounds.
 #[derive(Debug)]
 pub struct Metrics {
+	provider: SdkMeterProvider,
 	meter: Meter,
 	counters: HashMap<&'static str, Counter<u64>>,
+	histograms: Mutex<HashMap<Cow<'static, str>, Histogram<f64>>>,
+	histogram_boundaries: HistogramBoundaries,
 	attributes: MetricAttributes,
 	metric_buffer: Arc<Mutex<Vec<MetricValue>>>,
 	counter_buffer: Arc<Mutex<Vec<MetricCounter>>>,
@@ -38,7 +55,110 @@ pub struct MetricAttributes {
 	pub network: String,
 }
 
+/// Known client roles reported in metric attributes
+const ROLES: &[&str] = &["lightnode", "fatnode", "crawler"];
+
+/// Partition size attribute value when partition is not configured
+const NO_PARTITION: &str = "n/a";
+
+impl MetricAttributes {
+	pub fn builder() -> MetricAttributesBuilder {
+		MetricAttributesBuilder::default()
+	}
+}
+
+/// Builder which validates metric attributes before they are used to initialize metrics.
+#[derive(Debug, Default)]
+pub struct MetricAttributesBuilder {
+	role: Option<String>,
+	peer_id: Option<String>,
+	origin: Option<Origin>,
+	avail_address: Option<String>,
+	operating_mode: Option<String>,
+	partition_size: Option<String>,
+	network: Option<String>,
+}
+
+impl MetricAttributesBuilder {
+	pub fn role(mut self, role: impl Into<String>) -> Self {
+		self.role = Some(role.into());
+		self
+	}
+
+	pub fn peer_id(mut self, peer_id: impl Into<String>) -> Self {
+		self.peer_id = Some(peer_id.into());
+		self
+	}
+
+	pub fn origin(mut self, origin: Origin) -> Self {
+		self.origin = Some(origin);
+		self
+	}
+
+	pub fn avail_address(mut self, avail_address: impl Into<String>) -> Self {
+		self.avail_address = Some(avail_address.into());
+		self
+	}
+
+	pub fn operating_mode(mut self, operating_mode: impl Into<String>) -> Self {
+		self.operating_mode = Some(operating_mode.into());
+		self
+	}
+
+	/// Sets partition size in `number/fraction` format (default: `n/a`).
+	pub fn partition_size(mut self, partition_size: impl Into<String>) -> Self {
+		self.partition_size = Some(partition_size.into());
+		self
+	}
+
+	pub fn network(mut self, network: impl Into<String>) -> Self {
+		self.network = Some(network.into());
+		self
+	}
+
+	/// Validates attributes and builds [`MetricAttributes`].
+	pub fn build(self) -> Result<MetricAttributes> {
+		let role = self.role.ok_or_else(|| eyre!("Role is not set"))?;
+		if !ROLES.contains(&role.as_str()) {
+			return Err(eyre!("Unknown role: {role}"));
+		}
+
+		let peer_id = self.peer_id.ok_or_else(|| eyre!("Peer ID is not set"))?;
+		PeerId::from_str(&peer_id).wrap_err_with(|| format!("Invalid peer ID: {peer_id}"))?;
+
+		let partition_size = self
+			.partition_size
+			.unwrap_or_else(|| NO_PARTITION.to_string());
+		if partition_size != NO_PARTITION {
+			block_matrix_partition_format::parse(&partition_size)
+				.map_err(|error| eyre!("Invalid partition size {partition_size}: {error}"))?;
+		}
+
+		Ok(MetricAttributes {
+			role,
+			peer_id,
+			origin: self.origin.ok_or_else(|| eyre!("Origin is not set"))?,
+			avail_address: self
+				.avail_address
+				.ok_or_else(|| eyre!("Avail address is not set"))?,
+			operating_mode: self
+				.operating_mode
+				.ok_or_else(|| eyre!("Operating mode is not set"))?,
+			partition_size,
+			network: self.network.ok_or_else(|| eyre!("Network is not set"))?,
+		})
+	}
+}
+
 impl Metrics {
+	/// Flushes buffered metrics and shuts down the meter provider,
+	/// which exports all pending metrics to the collector.
+	pub async fn shutdown(&self) -> Result<()> {
+		super::Metrics::flush(self).await?;
+		self.provider.shutdown()?;
+		Ok(())
+	}
+
 	fn attributes(&self) -> [KeyValue; ATTRIBUTE_NUMBER] {
 		[
 			KeyValue::new("version", clap::crate_version!()),
@@ -52,7 +172,7 @@ impl Metrics {
 		]
 	}
 
-	async fn record_u64(&self, name: &'static str, value: u64) -> Result<()> {
+	async fn record_u64(&self, name: Cow<'static, str>, value: u64) -> Result<()> {
 		let instrument = self.meter.u64_observable_gauge(name).try_init()?;
 		let attributes = self.attributes();
 		self.meter
@@ -62,7 +182,7 @@ impl Metrics {
 		Ok(())
 	}
 
-	async fn record_f64(&self, name: &'static str, value: f64) -> Result<()> {
+	async fn record_f64(&self, name: Cow<'static, str>, value: f64) -> Result<()> {
 		let instrument = self.meter.f64_observable_gauge(name).try_init()?;
 		let attributes = self.attributes();
 		self.meter
@@ -71,11 +191,60 @@ impl Metrics {
 			})?;
 		Ok(())
 	}
+
+	/// Records observation into the histogram, which is created on first use.
+	/// Bucket boundaries are registered before the instrument is created,
+	/// so the histogram view can apply them.
+	async fn record_histogram_f64(
+		&self,
+		name: Cow<'static, str>,
+		value: f64,
+		boundaries: Vec<f64>,
+	) -> Result<()> {
+		let mut histograms = self.histograms.lock().await;
+		if !histograms.contains_key(&name) {
+			self.histogram_boundaries
+				.write()
+				.map_err(|_| eyre!("Histogram boundaries lock is poisoned"))?
+				.insert(name.clone(), boundaries);
+			let histogram = self.meter.f64_histogram(name.clone()).try_init()?;
+			histograms.insert(name.clone(), histogram);
+		}
+		histograms[&name].record(value, &self.attributes());
+		Ok(())
+	}
 }
 
-enum Record {
-	MaxU64(&'static str, u64),
-	AvgF64(&'static str, f64),
+/// Explicit bucket boundaries of histograms, by metric name
+type HistogramBoundaries = Arc<RwLock<HashMap<Cow<'static, str>, Vec<f64>>>>;
+
+/// Creates a view which aggregates histograms into buckets with registered boundaries.
+/// Instruments without registered boundaries use the default aggregation.
+fn histogram_view(boundaries: HistogramBoundaries) -> impl View {
+	move |instrument: &Instrument| {
+		let boundaries = boundaries
+			.read()
+			.ok()?
+			.get(instrument.name.as_ref())?
+			.clone();
+		let aggregation = Aggregation::ExplicitBucketHistogram {
+			boundaries,
+			record_min_max: true,
+		};
+		let stream = Stream::new()
+			.name(instrument.name.clone())
+			.description(instrument.description.clone())
+			.unit(instrument.unit.clone())
+			.aggregation(aggregation);
+		Some(stream)
+	}
+}
+
+/// Metric value with the aggregation used when metrics are flushed
+pub(super) enum Record {
+	MaxU64(Cow<'static, str>, u64),
+	AvgF64(Cow<'static, str>, f64),
+	HistogramF64(Cow<'static, str>, f64, Vec<f64>),
 }
 
 impl From<MetricValue> for Record {
@@ -84,6 +253,9 @@ impl From<MetricValue> for Record {
 		use Record::*;
 
 		let name = value.name();
+		let duration = |name, duration: Duration| {
+			HistogramF64(name, duration.as_secs_f64(), DURATION_BOUNDARIES.to_vec())
+		};
 
 		match value {
 			BlockHeight(number) => MaxU64(name, number as u64),
@@ -95,19 +267,35 @@ impl From<MetricValue> for Record {
 
 			DHTFetched(number) => AvgF64(name, number),
 			DHTFetchedPercentage(number) => AvgF64(name, number),
-			DHTFetchDuration(number) => AvgF64(name, number),
-			DHTPutDuration(number) => AvgF64(name, number),
+			DHTFetchDuration(number) => duration(name, number),
+			DHTPutDuration(number) => duration(name, number),
 			DHTPutSuccess(number) => AvgF64(name, number),
 
 			DHTConnectedPeers(number) => AvgF64(name, number as f64),
+			PeerCountBelowThreshold(below) => MaxU64(name, below as u64),
+			PeerDiscoveryRate(number) => AvgF64(name, number),
+			PeerConnectionRate(number) => AvgF64(name, number),
+			PeerChurnRate(number) => AvgF64(name, number),
+			BootstrapConnected(number) => AvgF64(name, number as f64),
 			DHTQueryTimeout(number) => AvgF64(name, number as f64),
 			DHTPingLatency(number) => AvgF64(name, number),
 
 			RPCFetched(number) => AvgF64(name, number),
-			RPCFetchDuration(number) => AvgF64(name, number),
-			RPCCallDuration(number) => AvgF64(name, number),
+			RPCFetchDuration(number) => duration(name, number),
+			RPCCallDuration(number) => duration(name, number),
+			NodeConnectionAttempts(number) => MaxU64(name, number as u64),
+			NodeConnectionRetries(number) => MaxU64(name, number as u64),
+			RPCHeaderCacheHitRate(number) => AvgF64(name, number),
+			RPCOpenCircuits(number) => MaxU64(name, number as u64),
+			// Average over the flush interval is a share of fetches where RPC was first
+			CellFetchSource(source) => AvgF64(name, source as u8 as f64),
 
 			Up() => MaxU64(name, 1),
+			MaintenanceLastRun(number) => MaxU64(name, number as u64),
+
+			CustomHistogram(_, number, boundaries) => HistogramF64(name, number, boundaries),
+			CustomGaugeF64 { value, .. } => AvgF64(name, value),
+			CustomGaugeU64 { value, .. } => MaxU64(name, value),
 
 			#[cfg(feature = "crawl")]
 			CrawlCellsSuccessRate(number) => AvgF64(name, number),
@@ -138,14 +326,18 @@ fn flatten_counters(buffer: &[impl MetricName]) -> HashMap<&'static str, u64> {
 /// and values are the aggregations (avg, max, etc.) of those metrics.
 fn flatten_metrics(
 	buffer: &[impl Into<Record> + Clone],
-) -> (HashMap<&'static str, u64>, HashMap<&'static str, f64>) {
-	let mut u64_maximums: HashMap<&'static str, Vec<u64>> = HashMap::new();
-	let mut f64_averages: HashMap<&'static str, Vec<f64>> = HashMap::new();
+) -> (
+	HashMap<Cow<'static, str>, u64>,
+	HashMap<Cow<'static, str>, f64>,
+) {
+	let mut u64_maximums: HashMap<Cow<'static, str>, Vec<u64>> = HashMap::new();
+	let mut f64_averages: HashMap<Cow<'static, str>, Vec<f64>> = HashMap::new();
 
 	for value in buffer {
 		match value.clone().into() {
 			Record::MaxU64(name, number) => u64_maximums.entry(name).or_default().push(number),
 			Record::AvgF64(name, number) => f64_averages.entry(name).or_default().push(number),
+			Record::HistogramF64(..) => (),
 		}
 	}
 
@@ -162,30 +354,52 @@ fn flatten_metrics(
 	(u64_metrics, f64_metrics)
 }
 
+/// Returns histogram observations from the provided buffer,
+/// along with the bucket boundaries of each histogram.
+fn histogram_observations(
+	buffer: &[impl Into<Record> + Clone],
+) -> Vec<(Cow<'static, str>, f64, Vec<f64>)> {
+	buffer
+		.iter()
+		.filter_map(|value| match value.clone().into() {
+			Record::HistogramF64(name, number, boundaries) => Some((name, number, boundaries)),
+			_ => None,
+		})
+		.collect()
+}
+
 #[async_trait]
 impl super::Metrics for Metrics {
 	/// Puts counter to the counter buffer if it is allowed.
 	/// If counter is not buffered, counter is incremented.
-	async fn count(&self, counter: super::MetricCounter) {
+	async fn count(&self, counter: super::MetricCounter) -> Result<(), MetricError> {
 		if !counter.is_allowed(&self.attributes.origin) {
-			return;
+			return Ok(());
 		}
 		if !counter.is_buffered() {
-			self.counters[&counter.name()].add(1, &self.attributes());
-			return;
+			let Some(instrument) = self.counters.get(&counter.name()) else {
+				let message = format!("Counter {} is not initialized", counter.name());
+				return Err(MetricError::InstrumentError(message));
+			};
+			instrument.add(1, &self.attributes());
+			return Ok(());
 		}
 		let mut counter_buffer = self.counter_buffer.lock().await;
 		counter_buffer.push(counter);
+		Ok(())
 	}
 
 	/// Puts metric to the metric buffer if it is allowed.
-	async fn record(&self, value: super::MetricValue) {
+	/// Custom metrics using reserved names are rejected.
+	async fn record(&self, value: super::MetricValue) -> Result<(), MetricError> {
+		value.validate_name()?;
 		if !value.is_allowed(&self.attributes.origin) {
-			return;
+			return Ok(());
 		}
 
 		let mut metric_buffer = self.metric_buffer.lock().await;
 		metric_buffer.push(value);
+		Ok(())
 	}
 
 	/// Calculates counters and average metrics, and flushes buffers to the collector.
@@ -196,6 +410,7 @@ impl super::Metrics for Metrics {
 
 		let mut metric_buffer = self.metric_buffer.lock().await;
 		let (metrics_u64, metrics_f64) = flatten_metrics(&metric_buffer);
+		let histograms = histogram_observations(&metric_buffer);
 		metric_buffer.clear();
 
 		for (counter, value) in counters {
@@ -211,25 +426,27 @@ impl super::Metrics for Metrics {
 			self.record_f64(metric, value).await?;
 		}
 
+		for (metric, value, boundaries) in histograms.into_iter() {
+			self.record_histogram_f64(metric, value, boundaries).await?;
+		}
+
+		Ok(())
+	}
+
+	/// Flushes buffers and forces the meter provider to export all pending metrics,
+	/// which should be done before short-lived processes exit.
+	async fn flush_all(&self) -> Result<()> {
+		super::Metrics::flush(self).await?;
+		self.provider.force_flush(&Context::current())?;
 		Ok(())
 	}
 }
 
 fn init_counters(meter: Meter, origin: Origin) -> HashMap<&'static str, Counter<u64>> {
-	[
-		MetricCounter::Starts,
-		MetricCounter::SessionBlocks,
-		MetricCounter::OutgoingConnectionErrors,
-		MetricCounter::IncomingConnectionErrors,
-		MetricCounter::IncomingConnections,
-		MetricCounter::EstablishedConnections,
-		MetricCounter::IncomingPutRecord,
-		MetricCounter::IncomingGetRecord,
-	]
-	.iter()
-	.filter(|counter| MetricCounter::is_allowed(counter, &origin))
-	.map(|counter| (counter.name(), meter.u64_counter(counter.name()).init()))
-	.collect()
+	MetricCounter::iter()
+		.filter(|counter| MetricCounter::is_allowed(counter, &origin))
+		.map(|counter| (counter.name(), meter.u64_counter(counter.name()).init()))
+		.collect()
 }
 
 pub fn initialize(
@@ -243,26 +460,39 @@ pub fn initialize(
 		timeout: Duration::from_secs(10),
 		protocol: Protocol::Grpc,
 	};
-	let provider = opentelemetry_otlp::new_pipeline()
-		.metrics(opentelemetry_sdk::runtime::Tokio)
-		.with_exporter(
-			opentelemetry_otlp::new_exporter()
-				.tonic()
-				.with_export_config(export_config),
-		)
-		.with_period(Duration::from_secs(ot_config.ot_export_period)) // Configures the intervening time between exports
+	let exporter = MetricsExporterBuilder::from(
+		opentelemetry_otlp::new_exporter()
+			.tonic()
+			.with_export_config(export_config),
+	)
+	.build_metrics_exporter(
+		Box::new(DefaultTemporalitySelector::new()),
+		Box::new(DefaultAggregationSelector::new()),
+	)?;
+	let reader = PeriodicReader::builder(exporter, opentelemetry_sdk::runtime::Tokio)
+		.with_interval(Duration::from_secs(ot_config.ot_export_period)) // Configures the intervening time between exports
 		.with_timeout(Duration::from_secs(ot_config.ot_export_timeout)) // Configures the time a OT waits for an export to complete before canceling it.
-		.build()?;
+		.build();
 
-	global::set_meter_provider(provider);
+	// OTLP metrics pipeline doesn't support views, so the provider is built directly
+	let histogram_boundaries = HistogramBoundaries::default();
+	let provider = SdkMeterProvider::builder()
+		.with_reader(reader)
+		.with_view(histogram_view(histogram_boundaries.clone()))
+		.build();
+
+	global::set_meter_provider(provider.clone());
 	let meter = global::meter("avail_light_client");
 
 	// Initialize counters - they need to persist unlike Gauges that are recreated on every record
 	let counters = init_counters(meter.clone(), origin);
 	Ok(Metrics {
+		provider,
 		meter,
 		attributes,
 		counters,
+		histograms: Mutex::new(HashMap::new()),
+		histogram_boundaries,
 		metric_buffer: Arc::new(Mutex::new(vec![])),
 		counter_buffer: Arc::new(Mutex::new(vec![])),
 	})
@@ -271,6 +501,143 @@ pub fn initialize(
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::telemetry::Metrics as _;
+	use opentelemetry_api::metrics::MeterProvider as _;
+	use opentelemetry_sdk::metrics::{
+		data::{self, ResourceMetrics, Temporality},
+		reader::{AggregationSelector, MetricProducer, MetricReader, TemporalitySelector},
+		InstrumentKind, ManualReader, Pipeline,
+	};
+	use std::sync::Weak;
+
+	fn test_attributes() -> MetricAttributesBuilder {
+		MetricAttributes::builder()
+			.role("lightnode")
+			.peer_id(PeerId::random().to_string())
+			.origin(Origin::Internal)
+			.avail_address("avail_address")
+			.operating_mode("client")
+			.network("local")
+	}
+
+	#[test]
+	fn test_metric_attributes_builder() {
+		let attributes = test_attributes().build().unwrap();
+		assert_eq!(attributes.partition_size, "n/a");
+
+		let attributes = test_attributes().partition_size("1/10").build().unwrap();
+		assert_eq!(attributes.partition_size, "1/10");
+
+		assert!(test_attributes().role("unknown").build().is_err());
+		assert!(test_attributes().peer_id("peer_id").build().is_err());
+		assert!(test_attributes().partition_size("10/1").build().is_err());
+		assert!(test_attributes().partition_size("1x10").build().is_err());
+		assert!(MetricAttributes::builder().build().is_err());
+	}
+
+	/// Manual reader which can be collected after it is registered with the provider
+	#[derive(Clone, Debug, Default)]
+	struct SharedReader(Arc<ManualReader>);
+
+	impl TemporalitySelector for SharedReader {
+		fn temporality(&self, kind: InstrumentKind) -> Temporality {
+			self.0.temporality(kind)
+		}
+	}
+
+	impl AggregationSelector for SharedReader {
+		fn aggregation(&self, kind: InstrumentKind) -> Aggregation {
+			self.0.aggregation(kind)
+		}
+	}
+
+	impl MetricReader for SharedReader {
+		fn register_pipeline(&self, pipeline: Weak<Pipeline>) {
+			self.0.register_pipeline(pipeline)
+		}
+
+		fn register_producer(&self, producer: Box<dyn MetricProducer>) {
+			self.0.register_producer(producer)
+		}
+
+		fn collect(&self, rm: &mut ResourceMetrics) -> opentelemetry_api::metrics::Result<()> {
+			self.0.collect(rm)
+		}
+
+		fn force_flush(&self, cx: &Context) -> opentelemetry_api::metrics::Result<()> {
+			self.0.force_flush(cx)
+		}
+
+		fn shutdown(&self) -> opentelemetry_api::metrics::Result<()> {
+			self.0.shutdown()
+		}
+	}
+
+	fn test_metrics() -> Metrics {
+		test_metrics_with_reader(SharedReader::default())
+	}
+
+	fn test_metrics_with_reader(reader: SharedReader) -> Metrics {
+		let histogram_boundaries = HistogramBoundaries::default();
+		let provider = SdkMeterProvider::builder()
+			.with_reader(reader)
+			.with_view(histogram_view(histogram_boundaries.clone()))
+			.build();
+		let meter = provider.meter("avail_light_client_test");
+		let counters = init_counters(meter.clone(), Origin::Internal);
+		Metrics {
+			provider,
+			meter,
+			counters,
+			histograms: Mutex::new(HashMap::new()),
+			histogram_boundaries,
+			attributes: test_attributes().build().unwrap(),
+			metric_buffer: Arc::new(Mutex::new(vec![])),
+			counter_buffer: Arc::new(Mutex::new(vec![])),
+		}
+	}
+
+	#[test]
+	fn test_init_counters() {
+		let meter = SdkMeterProvider::builder()
+			.build()
+			.meter("avail_light_client_test");
+		let counters = init_counters(meter.clone(), Origin::Internal);
+		assert_eq!(counters.len(), MetricCounter::iter().count());
+		assert!(MetricCounter::iter().all(|counter| counters.contains_key(&counter.name())));
+
+		let counters = init_counters(meter, Origin::External);
+		assert_eq!(counters.len(), 1);
+		assert!(counters.contains_key(&MetricCounter::Starts.name()));
+	}
+
+	#[tokio::test]
+	async fn test_flush_after_initialization() {
+		let metrics = test_metrics();
+		metrics.count(MetricCounter::SessionBlocks).await.unwrap();
+		metrics.record(MetricValue::BlockHeight(1)).await.unwrap();
+		assert_eq!(metrics.counter_buffer.lock().await.len(), 1);
+		assert_eq!(metrics.metric_buffer.lock().await.len(), 1);
+
+		metrics.shutdown().await.unwrap();
+		assert!(metrics.counter_buffer.lock().await.is_empty());
+		assert!(metrics.metric_buffer.lock().await.is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_flush_all() {
+		let metrics = test_metrics();
+		metrics.count(MetricCounter::SessionBlocks).await.unwrap();
+		metrics.record(MetricValue::BlockHeight(1)).await.unwrap();
+
+		metrics.flush_all().await.unwrap();
+		assert!(metrics.counter_buffer.lock().await.is_empty());
+		assert!(metrics.metric_buffer.lock().await.is_empty());
+
+		// Metrics can still be recorded after flush
+		metrics.record(MetricValue::BlockHeight(2)).await.unwrap();
+		assert_eq!(metrics.metric_buffer.lock().await.len(), 1);
+	}
 
 	#[test]
 	fn test_flatten_counters() {
@@ -351,12 +718,12 @@ mod tests {
 		let buffer = &[
 			MetricValue::DHTConnectedPeers(90),
 			MetricValue::Up(),
-			MetricValue::DHTFetchDuration(1.0),
+			MetricValue::DHTFetchDuration(Duration::from_secs_f64(1.0)),
 			MetricValue::DHTPutSuccess(10.0),
 			MetricValue::BlockConfidence(99.0),
 			MetricValue::Up(),
-			MetricValue::DHTFetchDuration(2.0),
-			MetricValue::DHTFetchDuration(2.1),
+			MetricValue::DHTFetchDuration(Duration::from_secs_f64(2.0)),
+			MetricValue::DHTFetchDuration(Duration::from_secs_f64(2.1)),
 			MetricValue::BlockHeight(999),
 			MetricValue::Up(),
 			MetricValue::DHTConnectedPeers(80),
@@ -366,10 +733,80 @@ mod tests {
 		assert_eq!(m_u64.len(), 2);
 		assert_eq!(m_u64.get("avail.light.up"), Some(&1));
 		assert_eq!(m_u64.get("avail.light.block.height"), Some(&999));
-		assert_eq!(m_f64.len(), 4);
+		assert_eq!(m_f64.len(), 3);
 		assert_eq!(m_f64.get("avail.light.dht.put_success"), Some(&10.0));
-		assert_eq!(m_f64.get("avail.light.dht.fetch_duration"), Some(&1.7));
+		assert_eq!(m_f64.get("avail.light.dht.fetch_duration"), None);
 		assert_eq!(m_f64.get("avail.light.block.confidence"), Some(&98.5));
 		assert_eq!(m_f64.get("avail.light.dht.connected_peers"), Some(&85.0));
 	}
+
+	#[test]
+	fn test_histogram_observations() {
+		assert!(histogram_observations(&[] as &[MetricValue]).is_empty());
+
+		let buffer = &[
+			MetricValue::BlockConfidence(90.0),
+			MetricValue::DHTFetchDuration(Duration::from_secs_f64(0.25)),
+			MetricValue::custom_histogram("custom", 2.0, vec![1.0, 2.0]),
+		];
+		assert_eq!(
+			histogram_observations(buffer),
+			vec![
+				(
+					"avail.light.dht.fetch_duration".into(),
+					0.25,
+					DURATION_BOUNDARIES.to_vec()
+				),
+				("custom".into(), 2.0, vec![1.0, 2.0]),
+			]
+		);
+	}
+
+	#[tokio::test]
+	async fn test_record_histograms() {
+		let reader = SharedReader::default();
+		let metrics = test_metrics_with_reader(reader.clone());
+		let buffer = [
+			MetricValue::DHTFetchDuration(Duration::from_secs_f64(0.25)),
+			MetricValue::DHTFetchDuration(Duration::from_secs_f64(3.0)),
+			MetricValue::DHTFetchDuration(Duration::from_secs_f64(100.0)),
+			MetricValue::custom_histogram("custom", 2.0, vec![1.0, 2.0]),
+			MetricValue::custom_histogram("custom", 1.5, vec![1.0, 2.0]),
+		];
+		for value in buffer {
+			metrics.record(value).await.unwrap();
+		}
+		metrics.flush().await.unwrap();
+
+		let mut resource_metrics = ResourceMetrics {
+			resource: Default::default(),
+			scope_metrics: vec![],
+		};
+		reader.collect(&mut resource_metrics).unwrap();
+		let histograms = resource_metrics.scope_metrics[0]
+			.metrics
+			.iter()
+			.filter_map(|metric| {
+				let histogram = metric
+					.data
+					.as_any()
+					.downcast_ref::<data::Histogram<f64>>()?;
+				Some((metric.name.as_ref(), &histogram.data_points[0]))
+			})
+			.collect::<HashMap<_, _>>();
+		assert_eq!(histograms.len(), 2);
+
+		let fetch_duration = histograms["avail.light.dht.fetch_duration"];
+		assert_eq!(fetch_duration.count, 3);
+		assert_eq!(fetch_duration.sum, 103.25);
+		assert_eq!(fetch_duration.bounds, DURATION_BOUNDARIES);
+		assert_eq!(
+			fetch_duration.bucket_counts,
+			vec![0, 1, 0, 0, 0, 1, 0, 0, 0, 1]
+		);
+
+		let custom = histograms["custom"];
+		assert_eq!(custom.bounds, vec![1.0, 2.0]);
+		assert_eq!(custom.bucket_counts, vec![0, 2, 0]);
+	}
 }
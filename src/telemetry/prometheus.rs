@@ -0,0 +1,189 @@
+use super::{otlp::Record, MetricCounter, MetricError, MetricName, MetricValue};
+use crate::types::Origin;
+use async_trait::async_trait;
+use color_eyre::{eyre::WrapErr, Result};
+use prometheus::{Encoder, Gauge, Histogram, HistogramOpts, IntCounter, Registry, TextEncoder};
+use std::{borrow::Cow, collections::HashMap, sync::Mutex};
+
+/// Metrics exposed in the Prometheus text format, to be scraped by the Prometheus server.
+///
+/// Unlike OTLP metrics, values are not buffered: gauges hold the last recorded value,
+/// and histograms and counters are updated immediately.
+pub struct Metrics {
+	registry: Registry,
+	origin: Origin,
+	counters: HashMap<&'static str, IntCounter>,
+	gauges: Mutex<HashMap<Cow<'static, str>, Gauge>>,
+	histograms: Mutex<HashMap<Cow<'static, str>, Histogram>>,
+}
+
+/// Converts metric name to the Prometheus format, which doesn't allow dots
+fn prometheus_name(name: &str) -> String {
+	name.replace('.', "_")
+}
+
+fn instrument_error(error: prometheus::Error) -> MetricError {
+	MetricError::InstrumentError(error.to_string())
+}
+
+impl Metrics {
+	pub fn new(origin: Origin) -> Result<Self> {
+		let registry = Registry::new();
+
+		// Counters are registered upfront, so they are exposed even before the first increment
+		let mut counters = HashMap::new();
+		for counter in MetricCounter::iter().filter(|counter| counter.is_allowed(&origin)) {
+			let name = counter.name();
+			let instrument = IntCounter::new(prometheus_name(name), name)?;
+			registry
+				.register(Box::new(instrument.clone()))
+				.wrap_err_with(|| format!("Unable to register counter {name}"))?;
+			counters.insert(name, instrument);
+		}
+
+		Ok(Metrics {
+			registry,
+			origin,
+			counters,
+			gauges: Default::default(),
+			histograms: Default::default(),
+		})
+	}
+
+	fn gauge(&self, name: Cow<'static, str>) -> Result<Gauge, MetricError> {
+		let mut gauges = self.gauges.lock().map_err(|_| MetricError::LockPoisoned)?;
+		if let Some(gauge) = gauges.get(&name) {
+			return Ok(gauge.clone());
+		}
+		let gauge = Gauge::new(prometheus_name(&name), name.as_ref()).map_err(instrument_error)?;
+		self.registry
+			.register(Box::new(gauge.clone()))
+			.map_err(instrument_error)?;
+		gauges.insert(name, gauge.clone());
+		Ok(gauge)
+	}
+
+	/// Returns histogram with the given name, bucket boundaries are set on the first use
+	fn histogram(
+		&self,
+		name: Cow<'static, str>,
+		boundaries: Vec<f64>,
+	) -> Result<Histogram, MetricError> {
+		let mut histograms = self
+			.histograms
+			.lock()
+			.map_err(|_| MetricError::LockPoisoned)?;
+		if let Some(histogram) = histograms.get(&name) {
+			return Ok(histogram.clone());
+		}
+		let opts = HistogramOpts::new(prometheus_name(&name), name.as_ref()).buckets(boundaries);
+		let histogram = Histogram::with_opts(opts).map_err(instrument_error)?;
+		self.registry
+			.register(Box::new(histogram.clone()))
+			.map_err(instrument_error)?;
+		histograms.insert(name, histogram.clone());
+		Ok(histogram)
+	}
+
+	/// Encodes all registered metrics in the Prometheus text format
+	pub fn encode(&self) -> Result<String> {
+		let mut buffer = vec![];
+		TextEncoder::new()
+			.encode(&self.registry.gather(), &mut buffer)
+			.wrap_err("Unable to encode metrics")?;
+		String::from_utf8(buffer).wrap_err("Encoded metrics are not valid UTF-8")
+	}
+}
+
+#[async_trait]
+impl super::Metrics for Metrics {
+	async fn count(&self, counter: MetricCounter) -> Result<(), MetricError> {
+		if !counter.is_allowed(&self.origin) {
+			return Ok(());
+		}
+		let Some(instrument) = self.counters.get(&counter.name()) else {
+			let message = format!("Counter {} is not initialized", counter.name());
+			return Err(MetricError::InstrumentError(message));
+		};
+		instrument.inc();
+		Ok(())
+	}
+
+	/// Updates the metric if it is allowed.
+	/// Custom metrics using reserved names are rejected.
+	async fn record(&self, value: MetricValue) -> Result<(), MetricError> {
+		value.validate_name()?;
+		if !value.is_allowed(&self.origin) {
+			return Ok(());
+		}
+
+		match value.into() {
+			Record::MaxU64(name, number) => self.gauge(name)?.set(number as f64),
+			Record::AvgF64(name, number) => self.gauge(name)?.set(number),
+			Record::HistogramF64(name, number, boundaries) => {
+				self.histogram(name, boundaries)?.observe(number)
+			},
+		}
+		Ok(())
+	}
+
+	/// Metrics are scraped, so there is nothing to flush
+	async fn flush(&self) -> Result<()> {
+		Ok(())
+	}
+
+	async fn flush_all(&self) -> Result<()> {
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::telemetry::Metrics as _;
+	use std::time::Duration;
+
+	#[tokio::test]
+	async fn test_encode() {
+		let metrics = Metrics::new(Origin::Internal).unwrap();
+		metrics.count(MetricCounter::Starts).await.unwrap();
+		metrics.record(MetricValue::BlockHeight(10)).await.unwrap();
+		metrics.record(MetricValue::BlockHeight(12)).await.unwrap();
+		metrics
+			.record(MetricValue::RPCCallDuration(Duration::from_millis(300)))
+			.await
+			.unwrap();
+
+		let encoded = metrics.encode().unwrap();
+		assert!(encoded.contains("avail_light_starts 1"));
+		assert!(encoded.contains("avail_light_block_height 12"));
+		assert!(encoded.contains("avail_light_rpc_call_duration_bucket{le=\"0.5\"} 1"));
+		assert!(encoded.contains("avail_light_rpc_call_duration_count 1"));
+	}
+
+	#[tokio::test]
+	async fn test_external_origin() {
+		let metrics = Metrics::new(Origin::External).unwrap();
+		metrics.count(MetricCounter::SessionBlocks).await.unwrap();
+		metrics.record(MetricValue::BlockHeight(10)).await.unwrap();
+		metrics.record(MetricValue::Up()).await.unwrap();
+
+		let encoded = metrics.encode().unwrap();
+		assert!(!encoded.contains("avail_light_session_blocks"));
+		assert!(!encoded.contains("avail_light_block_height"));
+		assert!(encoded.contains("avail_light_up 1"));
+	}
+
+	#[tokio::test]
+	async fn test_reserved_name() {
+		let metrics = Metrics::new(Origin::Internal).unwrap();
+		let value = MetricValue::CustomGaugeU64 {
+			name: "avail.light.up".to_string(),
+			value: 1,
+		};
+		assert!(matches!(
+			metrics.record(value).await,
+			Err(MetricError::ReservedName(name)) if name == "avail.light.up"
+		));
+	}
+}
@@ -1,7 +1,14 @@
 //! Shared light client structs and enums.
-use crate::network::p2p::{MemoryStoreConfig, ProvidersConfig, RocksDBStoreConfig};
-use crate::network::rpc::{Event, Node as RpcNode};
-use crate::utils::{extract_app_lookup, extract_kate};
+use crate::consts::{MAX_BLOCK_COLUMNS, MAX_BLOCK_ROWS};
+use crate::data::Database;
+use crate::network::p2p::{
+	MemoryStoreConfig, ProvidersConfig, ReputationConfig, RocksDBStoreConfig,
+};
+use crate::network::rpc::{
+	self, CircuitBreakerConfig, ConfidenceCalculator, DefaultConfidenceCalculator, Event,
+	Node as RpcNode,
+};
+use crate::utils::{extract_app_lookup, extract_kate, HeaderExt};
 use avail_core::DataLookup;
 use avail_subxt::{primitives::Header as DaHeader, utils::H256};
 use clap::{Parser, ValueEnum};
@@ -10,34 +17,40 @@ use color_eyre::{
 	eyre::{eyre, WrapErr},
 	Report, Result,
 };
+use futures::future::join_all;
 use kate_recovery::{
 	commitments,
 	matrix::{Dimensions, Partition},
 };
 use libp2p::kad::Mode as KadMode;
-use libp2p::{Multiaddr, PeerId};
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
 use semver::Version;
 use serde::{de::Error, Deserialize, Serialize};
 use sp_core::crypto::Ss58Codec;
-use sp_core::{blake2_256, bytes, ed25519};
+use sp_core::{bytes, ed25519};
 use std::fmt::{self, Display, Formatter};
-use std::fs;
-use std::num::{NonZeroU8, NonZeroUsize};
-use std::ops::Range;
+use std::num::{NonZeroU32, NonZeroU8, NonZeroUsize};
+use std::ops::{Deref, Range, RangeInclusive};
+use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use std::{env, fs};
 use subxt_signer::bip39::{Language, Mnemonic};
 use subxt_signer::sr25519::Keypair;
 use subxt_signer::{SecretString, SecretUri};
+use tokio::net::TcpStream;
 use tokio::sync::broadcast;
 use tokio_retry::strategy::{jitter, ExponentialBackoff, FibonacciBackoff};
-use tracing::warn;
+use tracing::{warn, Level};
+use tracing_subscriber::EnvFilter;
 
 const CELL_SIZE: usize = 32;
 const PROOF_SIZE: usize = 48;
 pub const CELL_WITH_PROOF_SIZE: usize = CELL_SIZE + PROOF_SIZE;
 
 const MINIMUM_SUPPORTED_VERSION: &str = "1.9.2";
+const DEFAULT_MAX_CELLS_PER_RPC: usize = 30;
 pub const DEV_FLAG_GENHASH: &str = "DEV";
 pub const IDENTITY_PROTOCOL: &str = "/avail_kad/id/1.0.0";
 pub const IDENTITY_AGENT_BASE: &str = "avail-light-client";
@@ -92,6 +105,12 @@ pub struct CliOpts {
 	/// fraction and number of the block matrix part to fetch (e.g. 2/20 means second 1/20 part of a matrix) (default: None)
 	#[arg(long, value_parser = block_matrix_partition_format::parse)]
 	pub block_matrix_partition: Option<Partition>,
+	/// Check connectivity and versions of the configured full nodes, and exit
+	#[arg(long)]
+	pub check_connections: bool,
+	/// Validate configuration and reachability of the bootstrap peers, and exit
+	#[arg(long)]
+	pub validate_config: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -120,6 +139,16 @@ pub struct BlockVerified {
 	pub block_num: u32,
 	pub extension: Option<Extension>,
 	pub confidence: Option<f64>,
+	/// Sampling details, set only if the block is verified by the light client
+	pub verification: Option<BlockVerificationResult>,
+}
+
+impl BlockVerified {
+	/// Checks if the block is verified with at least the given confidence
+	pub fn confidence_achieved(&self, threshold: f64) -> bool {
+		self.confidence
+			.is_some_and(|confidence| confidence >= threshold)
+	}
 }
 
 pub struct ClientChannels {
@@ -127,15 +156,31 @@ pub struct ClientChannels {
 	pub rpc_event_receiver: broadcast::Receiver<Event>,
 }
 
+/// Checks that matrix dimensions are powers of two and within protocol limits
+fn validate_dimensions(rows: u16, cols: u16) -> Result<Dimensions> {
+	if !rows.is_power_of_two() || rows > MAX_BLOCK_ROWS {
+		return Err(eyre!(
+			"Invalid number of rows {rows}, expected power of two not greater than {MAX_BLOCK_ROWS}"
+		));
+	}
+	if !cols.is_power_of_two() || cols > MAX_BLOCK_COLUMNS {
+		return Err(eyre!(
+			"Invalid number of columns {cols}, expected power of two not greater than {MAX_BLOCK_COLUMNS}"
+		));
+	}
+	Dimensions::new(rows, cols).ok_or_else(|| eyre!("Invalid dimensions {rows}x{cols}"))
+}
+
 impl TryFrom<(DaHeader, Option<f64>)> for BlockVerified {
 	type Error = Report;
 	fn try_from((header, confidence): (DaHeader, Option<f64>)) -> Result<Self, Self::Error> {
-		let hash: H256 = Encode::using_encoded(&header, blake2_256).into();
+		let hash = header.hash();
 		let mut block = BlockVerified {
 			header_hash: hash,
 			block_num: header.number,
 			extension: None,
 			confidence,
+			verification: None,
 		};
 
 		let Some((rows, cols, _, commitment)) = extract_kate(&header.extension) else {
@@ -148,8 +193,7 @@ impl TryFrom<(DaHeader, Option<f64>)> for BlockVerified {
 
 		if !lookup.is_empty() {
 			block.extension = Some(Extension {
-				dimensions: Dimensions::new(rows, cols)
-					.ok_or_else(|| eyre!("Invalid dimensions"))?,
+				dimensions: validate_dimensions(rows, cols)?,
 				lookup,
 				commitments: commitments::from_slice(&commitment)?,
 			});
@@ -198,6 +242,59 @@ impl TryFrom<String> for KademliaMode {
 	}
 }
 
+/// Order in which cells are fetched from DHT and RPC
+///
+/// * `DHTFirst` - fetch from DHT, missing cells are fetched from RPC
+/// * `RPCFirst` - fetch from RPC, missing cells are fetched from DHT
+/// * `Parallel` - fetch from both, first completed fetch is used and the other is cancelled,
+///   missing cells are then fetched from the other source
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub enum CellFetchStrategy {
+	#[default]
+	DHTFirst,
+	RPCFirst,
+	Parallel,
+}
+
+impl Display for CellFetchStrategy {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			CellFetchStrategy::DHTFirst => write!(f, "dht_first"),
+			CellFetchStrategy::RPCFirst => write!(f, "rpc_first"),
+			CellFetchStrategy::Parallel => write!(f, "parallel"),
+		}
+	}
+}
+
+impl TryFrom<String> for CellFetchStrategy {
+	type Error = color_eyre::Report;
+
+	fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+		match value.to_lowercase().as_str() {
+			"dht_first" => Ok(CellFetchStrategy::DHTFirst),
+			"rpc_first" => Ok(CellFetchStrategy::RPCFirst),
+			"parallel" => Ok(CellFetchStrategy::Parallel),
+			_ => Err(eyre!(
+				"Wrong cell fetch strategy. Expecting 'dht_first', 'rpc_first' or 'parallel'."
+			)),
+		}
+	}
+}
+
+impl From<CellFetchStrategy> for String {
+	fn from(value: CellFetchStrategy) -> Self {
+		value.to_string()
+	}
+}
+
+/// Source which provided cells first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellFetchSource {
+	DHT = 0,
+	RPC = 1,
+}
+
 /// Client mode
 ///
 /// * `LightClient` - light client is running
@@ -217,6 +314,59 @@ impl From<Option<u32>> for Mode {
 	}
 }
 
+impl Mode {
+	pub fn is_light_client(&self) -> bool {
+		matches!(self, Mode::LightClient)
+	}
+
+	pub fn is_app_client(&self) -> bool {
+		matches!(self, Mode::AppClient(_))
+	}
+
+	/// Returns application ID if app client is running
+	pub fn app_id(&self) -> Option<u32> {
+		match self {
+			Mode::LightClient => None,
+			Mode::AppClient(app_id) => Some(*app_id),
+		}
+	}
+}
+
+impl Display for Mode {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Mode::LightClient => write!(f, "light client"),
+			Mode::AppClient(app_id) => write!(f, "app client (app_id: {app_id})"),
+		}
+	}
+}
+
+/// Result of the block verification performed by the light client
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlockVerificationResult {
+	pub block_number: u32,
+	pub cells_sampled: u32,
+	pub cells_verified: u32,
+	pub confidence: f64,
+	pub mode: Mode,
+	pub duration: Duration,
+}
+
+impl Display for BlockVerificationResult {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"Block {} verified by {}: {}/{} cells, confidence {}%, duration {:?}",
+			self.block_number,
+			self.mode,
+			self.cells_verified,
+			self.cells_sampled,
+			self.confidence,
+			self.duration
+		)
+	}
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(try_from = "String")]
 pub enum Origin {
@@ -372,6 +522,15 @@ impl IntoIterator for RetryConfig {
 	}
 }
 
+impl RetryConfig {
+	/// Returns retry delays, each capped to the given maximum backoff duration
+	pub fn bounded(self, max_backoff: Duration) -> Vec<Duration> {
+		self.into_iter()
+			.map(|delay| delay.min(max_backoff))
+			.collect()
+	}
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ExponentialConfig {
 	pub base: u64,
@@ -386,6 +545,77 @@ pub struct FibonacciConfig {
 	pub retries: usize,
 }
 
+/// Sampling confidence in percents, guaranteed to be in range (50, 100)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[serde(try_from = "f64", into = "f64")]
+pub struct Confidence(f64);
+
+impl Confidence {
+	pub fn new(value: f64) -> Result<Self> {
+		if !(value > 50.0 && value < 100.0) {
+			return Err(eyre!(
+				"Invalid confidence {value}, expected value in range (50, 100)"
+			));
+		}
+		Ok(Confidence(value))
+	}
+}
+
+impl Deref for Confidence {
+	type Target = f64;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl TryFrom<f64> for Confidence {
+	type Error = Report;
+
+	fn try_from(value: f64) -> Result<Self, Self::Error> {
+		Confidence::new(value)
+	}
+}
+
+impl From<Confidence> for f64 {
+	fn from(confidence: Confidence) -> Self {
+		confidence.0
+	}
+}
+
+/// Errors returned while loading the configuration, which consumers can match on
+/// by downcasting the returned report (`report.downcast_ref::<ConfigError>()`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+	/// Configuration file cannot be read
+	Read { path: String, error: String },
+	/// Configuration file is not valid JSON or TOML
+	Parse { path: String, error: String },
+	/// Configuration parameter has invalid value
+	InvalidField {
+		field: &'static str,
+		reason: &'static str,
+	},
+}
+
+impl Display for ConfigError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			ConfigError::Read { path, error } => {
+				write!(f, "Failed to read configuration from {path}: {error}")
+			},
+			ConfigError::Parse { path, error } => {
+				write!(f, "Failed to load configuration from {path}: {error}")
+			},
+			ConfigError::InvalidField { field, reason } => {
+				write!(f, "Invalid `{field}` value, {reason}")
+			},
+		}
+	}
+}
+
+impl std::error::Error for ConfigError {}
+
 /// Representation of a configuration used by this project.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
@@ -425,11 +655,12 @@ pub struct RuntimeConfig {
 	pub genesis_hash: String,
 	/// If set, application client is started with given app_id (default: None).
 	pub app_id: Option<u32>,
-	/// Confidence threshold, used to calculate how many cells need to be sampled to achieve desired confidence (default: 92.0).
-	pub confidence: f64,
+	/// Confidence threshold, used to calculate how many cells need to be sampled to achieve desired confidence (default: 99.9).
+	pub confidence: Confidence,
 	/// File system path where RocksDB used by light client, stores its data.
 	pub avail_path: String,
 	/// Log level, default is `INFO`. See `<https://docs.rs/log/0.4.14/log/enum.LevelFilter.html>` for possible log level values. (default: `INFO`).
+	/// Filter directives like `avail_light=debug,libp2p=warn` are also supported.
 	pub log_level: String,
 	pub origin: Origin,
 	/// If set to true, logs are displayed in JSON format, which is used for structured logging. Otherwise, plain text format is used (default: false).
@@ -439,8 +670,17 @@ pub struct RuntimeConfig {
 	pub ot_export_period: u64,
 	pub ot_export_timeout: u64,
 	pub ot_flush_block_interval: u32,
+	/// Maximum duration of the maintenance step performed for each block, in seconds (default: 30).
+	pub maintenance_timeout: u64,
+	/// Interval of the Kademlia map shrinking during maintenance, in blocks (default: 10).
+	pub maintenance_interval: NonZeroU32,
+	/// Number of DHT peers below which a warning is logged during maintenance (default: 3).
+	pub peer_count_threshold: usize,
 	/// Disables fetching of cells from RPC, set to true if client expects cells to be available in DHT (default: false).
 	pub disable_rpc: bool,
+	/// Order of fetching cells from DHT and RPC: 'dht_first', 'rpc_first' or 'parallel' (default: 'dht_first').
+	/// Ignored if RPC is disabled.
+	pub cell_fetch_strategy: CellFetchStrategy,
 	/// Maximum number of parallel tasks spawned for GET and PUT operations on DHT (default: 20).
 	pub dht_parallelization_limit: usize,
 	/// Number of parallel queries for cell fetching via RPC from node (default: 8).
@@ -483,6 +723,18 @@ pub struct RuntimeConfig {
 	pub task_command_buffer_size: usize,
 	pub per_connection_event_buffer_size: usize,
 	pub dial_concurrency_factor: u8,
+	/// Peers with reputation score below this threshold are blocked, score is in range [0, 1] (default: 0.2).
+	pub peer_ban_threshold: f64,
+	/// Weight of the previous peer reputation score when connection outcome is recorded, in range [0, 1) (default: 0.9).
+	pub peer_score_decay: f64,
+	/// Interval of the peer reputation score recovery, in seconds. Blocked peers are unblocked once their score recovers (default: 60).
+	pub peer_score_recovery_interval: u64,
+	/// Sliding window of peer discovery, connection and churn rates, in seconds (default: 60).
+	pub peer_stats_window: u64,
+	/// Interval of bootstrap peers connectivity check, in blocks, 0 disables the check (default: 10).
+	pub bootstrap_check_interval: u32,
+	/// Timeout of the TCP connection to bootstrap peers when configuration is validated, in seconds (default: 5).
+	pub bootstrap_connect_timeout: u64,
 	/// Sets the timeout for a single Kademlia query. (default: 60s).
 	pub store_pruning_interval: u32,
 	/// Sets the allowed level of parallelism for iterative Kademlia queries. (default: 3).
@@ -510,15 +762,264 @@ pub struct RuntimeConfig {
 	///     retries: 6,
 	/// )
 	pub retry_config: RetryConfig,
+	/// Maximum duration of a single backoff delay between retries, in seconds (default: 60).
+	pub max_backoff_duration: u64,
+	/// Number of recently fetched block headers kept in memory (default: 64).
+	pub header_cache_capacity: NonZeroUsize,
+	/// Number of consecutive RPC failures after which the node is skipped (default: 5).
+	pub rpc_failure_threshold: u32,
+	/// Duration for which failing RPC node is skipped, in seconds (default: 30).
+	pub rpc_recovery_timeout: u64,
+	/// Timeout of the full node health check, performed on startup and with `--check-connections` flag, in seconds (default: 5).
+	pub full_node_probe_timeout: u64,
+	/// Model used to calculate number of sampled cells for the configured confidence.
+	/// Can be set only by library consumers, since it is not serialized (default: [`DefaultConfidenceCalculator`]).
+	#[serde(skip)]
+	pub confidence_calculator: Arc<dyn ConfidenceCalculator + Send + Sync>,
 	#[cfg(feature = "crawl")]
 	#[serde(flatten)]
 	pub crawl: crate::crawl_client::CrawlConfig,
 }
 
+/// Outcome of the bootstrap peer reachability check
+#[derive(Clone, Debug, PartialEq)]
+pub struct BootstrapResult {
+	pub peer_id: PeerId,
+	pub addr: Multiaddr,
+	pub reachable: bool,
+}
+
+/// Returns TCP host and port of the multiaddress, if any
+fn tcp_address(addr: &Multiaddr) -> Option<(String, u16)> {
+	let mut host = None;
+	let mut port = None;
+	for protocol in addr.iter() {
+		match protocol {
+			Protocol::Ip4(ip) => host = Some(ip.to_string()),
+			Protocol::Ip6(ip) => host = Some(ip.to_string()),
+			Protocol::Dns(name) | Protocol::Dns4(name) | Protocol::Dns6(name) => {
+				host = Some(name.to_string())
+			},
+			Protocol::Tcp(tcp_port) => port = Some(tcp_port),
+			_ => {},
+		}
+	}
+	Some((host?, port?))
+}
+
 impl RuntimeConfig {
 	pub fn is_fat_client(&self) -> bool {
 		self.block_matrix_partition.is_some()
 	}
+
+	/// Returns maximum number of cells per proof query, or the default if not configured.
+	pub fn max_cells_per_rpc(&self) -> usize {
+		self.max_cells_per_rpc.unwrap_or(DEFAULT_MAX_CELLS_PER_RPC)
+	}
+
+	/// Returns confidence which is actually used for sampling, since the configured
+	/// confidence is capped to 99.99 (see [`rpc::effective_confidence`]).
+	pub fn effective_confidence(&self) -> f64 {
+		rpc::effective_confidence(*self.confidence)
+	}
+
+	/// Returns configured full nodes which accept the WebSocket connection within the timeout.
+	/// Nodes are probed concurrently (see [`rpc::reachable_nodes`]).
+	pub async fn full_node_ws_healthy(&self, timeout: Duration) -> Vec<String> {
+		rpc::reachable_nodes(&self.full_node_ws, timeout).await
+	}
+
+	/// Checks if the configured bootstrap peers accept TCP connections within the timeout.
+	/// Unlike the periodic check during maintenance, this doesn't need a running P2P client.
+	/// Peers are checked concurrently, peers without TCP address are considered unreachable.
+	pub async fn validate_bootstraps(&self, connect_timeout: Duration) -> Vec<BootstrapResult> {
+		let checks = self.bootstraps.iter().map(|bootstrap| async move {
+			let (peer_id, addr): (PeerId, Multiaddr) = bootstrap.into();
+			let reachable = match tcp_address(&addr) {
+				Some(address) => matches!(
+					tokio::time::timeout(connect_timeout, TcpStream::connect(address)).await,
+					Ok(Ok(_))
+				),
+				None => false,
+			};
+			if !reachable {
+				warn!(%peer_id, %addr, "Bootstrap peer is unreachable");
+			}
+			BootstrapResult {
+				peer_id,
+				addr,
+				reachable,
+			}
+		});
+		join_all(checks).await
+	}
+
+	/// Returns log filter for the configured `log_level`.
+	/// Simple level (e.g. `INFO`) applies to the light client logs only,
+	/// while directives (e.g. `avail_light=debug,libp2p=warn`) are used as provided.
+	/// Value is treated as directives only if it contains `=` or `,`, so misspelled level is an error.
+	pub fn log_filter_directives(&self) -> Result<EnvFilter> {
+		if self.log_level.contains(['=', ',']) {
+			return EnvFilter::try_new(&self.log_level)
+				.wrap_err_with(|| format!("Invalid `log_level` directives: {}", self.log_level));
+		}
+		let level = self
+			.log_level
+			.to_uppercase()
+			.parse::<Level>()
+			.wrap_err_with(|| format!("Invalid `log_level` value: {}", self.log_level))?;
+		Ok(EnvFilter::new(format!("avail_light={level}")))
+	}
+
+	/// Loads configuration from the given file and validates it.
+	/// File with `.json` extension is parsed as JSON, any other file is parsed as TOML.
+	/// Parameters missing from the file are set to their default values.
+	pub fn from_file(path: &Path) -> Result<RuntimeConfig> {
+		let display_path = path.display().to_string();
+		let content = fs::read_to_string(path).map_err(|error| ConfigError::Read {
+			path: display_path.clone(),
+			error: error.to_string(),
+		})?;
+
+		let cfg: RuntimeConfig = match path.extension().and_then(|extension| extension.to_str()) {
+			Some("json") => serde_json::from_str(&content).map_err(|error| error.to_string()),
+			_ => toml::from_str(&content).map_err(|error| error.to_string()),
+		}
+		.map_err(|error| ConfigError::Parse {
+			path: display_path.clone(),
+			error,
+		})?;
+
+		cfg.validate()
+			.wrap_err(format!("Invalid configuration in {display_path}"))?;
+		Ok(cfg)
+	}
+
+	/// Merges override configuration on top of this one.
+	///
+	/// Precedence rules:
+	/// - Override field value is used if it differs from the default value of that field.
+	/// - Otherwise, value from this (base) configuration is kept.
+	/// - Nested structures (e.g. `retry_config`) are merged field by field, using the same rules.
+	/// - List fields (e.g. `full_node_ws`, `bootstraps`) are replaced as a whole, never appended.
+	/// - Optional fields which are set in the override replace the base value as a whole.
+	///
+	/// Consequently, override cannot reset base field to its default value.
+	/// Confidence calculator is not serialized, so the base one is kept.
+	pub fn merge(&self, override_config: &RuntimeConfig) -> RuntimeConfig {
+		use serde_json::Value;
+
+		fn has_same_keys(
+			first: &serde_json::Map<String, Value>,
+			second: &serde_json::Map<String, Value>,
+		) -> bool {
+			first.len() == second.len() && first.keys().all(|key| second.contains_key(key))
+		}
+
+		fn merge_value(base: &mut Value, override_value: Value, default: &Value) {
+			match (base, override_value, default) {
+				(Value::Object(base), Value::Object(override_map), Value::Object(default))
+					if has_same_keys(base, &override_map) && has_same_keys(base, default) =>
+				{
+					for (key, value) in override_map {
+						if let (Some(base), Some(default)) = (base.get_mut(&key), default.get(&key))
+						{
+							merge_value(base, value, default);
+						}
+					}
+				},
+				(base, override_value, default) => {
+					if &override_value != default {
+						*base = override_value;
+					}
+				},
+			}
+		}
+
+		let to_value =
+			|cfg: &RuntimeConfig| serde_json::to_value(cfg).expect("Configuration is serializable");
+		let mut merged = to_value(self);
+		merge_value(
+			&mut merged,
+			to_value(override_config),
+			&to_value(&RuntimeConfig::default()),
+		);
+
+		let mut merged: RuntimeConfig =
+			serde_json::from_value(merged).expect("Merged configuration is deserializable");
+		merged.confidence_calculator = self.confidence_calculator.clone();
+		merged
+	}
+
+	/// Overrides configuration parameters with values of `AVAIL_` prefixed environment variables.
+	///
+	/// Every configuration parameter can be overridden by variable named after the
+	/// upper-cased parameter name (e.g. `AVAIL_HTTP_SERVER_PORT` for `http_server_port`,
+	/// or `AVAIL_CONFIDENCE` for `confidence`). Values of non-string parameters are
+	/// parsed as JSON (e.g. `AVAIL_FULL_NODE_WS='["ws://127.0.0.1:9944"]'`).
+	/// Parameters without corresponding variable are left unchanged.
+	pub fn apply_env_overlay(&mut self) -> Result<()> {
+		self.apply_overlay(|name| env::var(name).ok())
+	}
+
+	fn apply_overlay(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+		const PREFIX: &str = "AVAIL_";
+
+		let serde_json::Value::Object(mut map) = serde_json::to_value(&*self)? else {
+			return Err(eyre!("Configuration is not serialized as a map"));
+		};
+
+		let mut overridden = false;
+		for (key, value) in map.iter_mut() {
+			let name = format!("{PREFIX}{}", key.to_uppercase());
+			let Some(variable) = var(&name) else {
+				continue;
+			};
+			overridden = true;
+			*value = match value {
+				serde_json::Value::String(_) => serde_json::Value::String(variable),
+				_ => serde_json::from_str(&variable).unwrap_or(serde_json::Value::String(variable)),
+			};
+		}
+
+		if !overridden {
+			return Ok(());
+		}
+
+		let mut cfg: RuntimeConfig = serde_json::from_value(serde_json::Value::Object(map))
+			.wrap_err("Failed to apply environment variables")?;
+		cfg.confidence_calculator = self.confidence_calculator.clone();
+		cfg.validate()
+			.wrap_err("Invalid configuration in environment variables")?;
+		*self = cfg;
+		Ok(())
+	}
+
+	fn validate(&self) -> Result<(), ConfigError> {
+		let invalid = |field, reason| Err(ConfigError::InvalidField { field, reason });
+		if self.full_node_ws.is_empty() {
+			return invalid("full_node_ws", "at least one endpoint is required");
+		}
+		if !(0.0..=1.0).contains(&self.peer_ban_threshold) {
+			return invalid("peer_ban_threshold", "must be in range [0, 1]");
+		}
+		if !(0.0..1.0).contains(&self.peer_score_decay) {
+			return invalid("peer_score_decay", "must be in range [0, 1)");
+		}
+		if self.peer_score_recovery_interval == 0 {
+			return invalid("peer_score_recovery_interval", "must be greater than 0");
+		}
+		if self.peer_stats_window == 0 {
+			return invalid("peer_stats_window", "must be greater than 0");
+		}
+		if self.rpc_failure_threshold == 0 {
+			return invalid("rpc_failure_threshold", "must be greater than 0");
+		}
+		if self.max_cells_per_rpc == Some(0) {
+			return invalid("max_cells_per_rpc", "must be greater than 0");
+		}
+		Ok(())
+	}
 }
 
 pub struct Delay(pub Option<Duration>);
@@ -527,6 +1028,8 @@ pub struct Delay(pub Option<Duration>);
 pub struct LightClientConfig {
 	pub confidence: f64,
 	pub block_processing_delay: Delay,
+	pub confidence_calculator: Arc<dyn ConfidenceCalculator + Send + Sync>,
+	pub mode: Mode,
 }
 
 impl Delay {
@@ -544,8 +1047,10 @@ impl From<&RuntimeConfig> for LightClientConfig {
 			.map(|v| Duration::from_secs(v.into()));
 
 		LightClientConfig {
-			confidence: val.confidence,
+			confidence: *val.confidence,
 			block_processing_delay: Delay(block_processing_delay),
+			confidence_calculator: val.confidence_calculator.clone(),
+			mode: Mode::from(val.app_id),
 		}
 	}
 }
@@ -570,13 +1075,13 @@ impl From<&RuntimeConfig> for FatClientConfig {
 
 		FatClientConfig {
 			full_nodes_ws: val.full_node_ws.clone(),
-			confidence: val.confidence,
+			confidence: *val.confidence,
 			disable_rpc: val.disable_rpc,
 			dht_parallelization_limit: val.dht_parallelization_limit,
 			query_proof_rpc_parallel_tasks: val.query_proof_rpc_parallel_tasks,
 			block_processing_delay: Delay(block_processing_delay),
 			block_matrix_partition: val.block_matrix_partition,
-			max_cells_per_rpc: val.max_cells_per_rpc.unwrap_or(30),
+			max_cells_per_rpc: val.max_cells_per_rpc(),
 		}
 	}
 }
@@ -595,6 +1100,11 @@ pub struct LibP2PConfig {
 	pub task_command_buffer_size: NonZeroUsize,
 	pub per_connection_event_buffer_size: usize,
 	pub dial_concurrency_factor: NonZeroU8,
+	pub peer_ban_threshold: f64,
+	pub peer_score_decay: f64,
+	pub peer_score_recovery_interval: Duration,
+	pub peer_stats_window: Duration,
+	pub bootstraps: Vec<(PeerId, Multiaddr)>,
 }
 
 impl From<&LibP2PConfig> for libp2p::kad::Config {
@@ -635,6 +1145,26 @@ impl From<&LibP2PConfig> for MemoryStoreConfig {
 	}
 }
 
+impl From<&LibP2PConfig> for ReputationConfig {
+	fn from(cfg: &LibP2PConfig) -> Self {
+		ReputationConfig {
+			ban_threshold: cfg.peer_ban_threshold,
+			decay: cfg.peer_score_decay,
+			// Bootstrap peers are never blocked, to keep the node connected to the network
+			exempt_peers: cfg.bootstraps.iter().map(|(peer_id, _)| *peer_id).collect(),
+		}
+	}
+}
+
+impl From<&RuntimeConfig> for CircuitBreakerConfig {
+	fn from(cfg: &RuntimeConfig) -> Self {
+		CircuitBreakerConfig {
+			failure_threshold: cfg.rpc_failure_threshold,
+			recovery_timeout: Duration::from_secs(cfg.rpc_recovery_timeout),
+		}
+	}
+}
+
 impl From<&LibP2PConfig> for RocksDBStoreConfig {
 	fn from(cfg: &LibP2PConfig) -> Self {
 		RocksDBStoreConfig {
@@ -664,6 +1194,11 @@ impl From<&RuntimeConfig> for LibP2PConfig {
 			per_connection_event_buffer_size: val.per_connection_event_buffer_size,
 			dial_concurrency_factor: std::num::NonZeroU8::new(val.dial_concurrency_factor)
 				.expect("Invalid dial concurrency factor"),
+			peer_ban_threshold: val.peer_ban_threshold,
+			peer_score_decay: val.peer_score_decay,
+			peer_score_recovery_interval: Duration::from_secs(val.peer_score_recovery_interval),
+			peer_stats_window: Duration::from_secs(val.peer_stats_window),
+			bootstraps: val.bootstraps.iter().map(Into::into).collect(),
 		}
 	}
 }
@@ -818,6 +1353,7 @@ impl AgentVersion {
 #[derive(Clone)]
 pub struct SyncClientConfig {
 	pub confidence: f64,
+	pub confidence_calculator: Arc<dyn ConfidenceCalculator + Send + Sync>,
 	pub disable_rpc: bool,
 	pub dht_parallelization_limit: usize,
 	pub is_last_step: bool,
@@ -826,7 +1362,8 @@ pub struct SyncClientConfig {
 impl From<&RuntimeConfig> for SyncClientConfig {
 	fn from(val: &RuntimeConfig) -> Self {
 		SyncClientConfig {
-			confidence: val.confidence,
+			confidence: *val.confidence,
+			confidence_calculator: val.confidence_calculator.clone(),
 			disable_rpc: val.disable_rpc,
 			dht_parallelization_limit: val.dht_parallelization_limit,
 			is_last_step: val.app_id.is_none(),
@@ -887,7 +1424,7 @@ impl Default for RuntimeConfig {
 			full_node_ws: vec!["ws://127.0.0.1:9944".to_owned()],
 			genesis_hash: "DEV".to_owned(),
 			app_id: None,
-			confidence: 99.9,
+			confidence: Confidence::new(99.9).expect("Valid default confidence"),
 			avail_path: "avail_path".to_owned(),
 			log_level: "INFO".to_owned(),
 			log_format_json: false,
@@ -895,14 +1432,18 @@ impl Default for RuntimeConfig {
 			ot_export_period: 300,
 			ot_export_timeout: 10,
 			ot_flush_block_interval: 15,
+			maintenance_timeout: 30,
+			maintenance_interval: NonZeroU32::new(10).expect("Valid default maintenance interval"),
+			peer_count_threshold: 3,
 			disable_rpc: false,
+			cell_fetch_strategy: CellFetchStrategy::DHTFirst,
 			dht_parallelization_limit: 20,
 			query_proof_rpc_parallel_tasks: 8,
 			block_processing_delay: Some(20),
 			block_matrix_partition: None,
 			sync_start_block: None,
 			sync_finality_enable: false,
-			max_cells_per_rpc: Some(30),
+			max_cells_per_rpc: Some(DEFAULT_MAX_CELLS_PER_RPC),
 			kad_record_ttl: 24 * 60 * 60,
 			threshold: 5000,
 			replication_factor: 5,
@@ -913,6 +1454,12 @@ impl Default for RuntimeConfig {
 			task_command_buffer_size: 32,
 			per_connection_event_buffer_size: 7,
 			dial_concurrency_factor: 8,
+			peer_ban_threshold: 0.2,
+			peer_score_decay: 0.9,
+			peer_score_recovery_interval: 60,
+			peer_stats_window: 60,
+			bootstrap_check_interval: 10,
+			bootstrap_connect_timeout: 5,
 			store_pruning_interval: 180,
 			query_timeout: 10,
 			query_parallelism: 3,
@@ -930,6 +1477,13 @@ impl Default for RuntimeConfig {
 				max_delay: 10,
 				retries: 6,
 			}),
+			max_backoff_duration: 60,
+			header_cache_capacity: NonZeroUsize::new(64)
+				.expect("Valid default header cache capacity"),
+			rpc_failure_threshold: 5,
+			rpc_recovery_timeout: 30,
+			full_node_probe_timeout: 5,
+			confidence_calculator: Arc::new(DefaultConfidenceCalculator),
 		}
 	}
 }
@@ -1040,11 +1594,12 @@ impl RuntimeConfig {
 	pub fn load_runtime_config(&mut self, opts: &CliOpts) -> Result<()> {
 		if let Some(config_path) = &opts.config {
 			fs::metadata(config_path).map_err(|_| eyre!("Provided config file doesn't exist."))?;
-			let cfg: RuntimeConfig = confy::load_path(config_path)
-				.wrap_err(format!("Failed to load configuration from {}", config_path))?;
-			*self = cfg;
+			*self = RuntimeConfig::from_file(Path::new(config_path))?;
 		}
 
+		// Environment variables override the config file parameters
+		self.apply_env_overlay()?;
+
 		// Flags override the config parameters
 		if let Some(network) = &opts.network {
 			let bootstrap: (PeerId, Multiaddr) = (
@@ -1177,6 +1732,65 @@ pub struct State {
 	pub connected_node: RpcNode,
 }
 
+/// Summary of the block processing progress, derived from [`State`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncStatus {
+	/// Latest block with achieved confidence, if any
+	pub latest_verified_block: Option<u32>,
+	/// Latest finalized block received from the node
+	pub chain_head: u32,
+	/// Whether historical blocks are still being synced
+	pub syncing: bool,
+	/// Number of blocks with stored verified cell count, since the client started
+	pub verified_blocks_in_window: u32,
+	/// Ranges of blocks with achieved confidence stored in the database, including gaps between them
+	pub verified_ranges: Vec<RangeInclusive<u32>>,
+}
+
+impl Display for SyncStatus {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self.latest_verified_block {
+			Some(block) => write!(f, "latest verified block {block}")?,
+			None => write!(f, "no verified blocks")?,
+		}
+		write!(
+			f,
+			", chain head {}, {} blocks verified",
+			self.chain_head, self.verified_blocks_in_window
+		)?;
+		if self.syncing {
+			write!(f, ", syncing")?;
+		}
+		Ok(())
+	}
+}
+
+impl State {
+	/// Returns sync status, with verified block ranges read from the database
+	pub fn sync_status(&self, db: &impl Database) -> Result<SyncStatus> {
+		let verified_ranges = db.verified_ranges()?;
+		// Blocks in the window without stored verified cell count are not counted
+		let verified_blocks_in_window = self.confidence_achieved.as_ref().map_or(0, |window| {
+			verified_ranges
+				.iter()
+				.map(|range| {
+					let first = (*range.start()).max(window.first);
+					let last = (*range.end()).min(window.last);
+					last.saturating_sub(first) + u32::from(first <= last)
+				})
+				.sum()
+		});
+
+		Ok(SyncStatus {
+			latest_verified_block: self.confidence_achieved.last(),
+			chain_head: self.latest,
+			syncing: self.synced == Some(false),
+			verified_blocks_in_window,
+			verified_ranges,
+		})
+	}
+}
+
 pub trait OptionBlockRange {
 	fn set(&mut self, block_number: u32);
 	fn first(&self) -> Option<u32>;
@@ -1263,3 +1877,446 @@ impl TimeToLive {
 		Instant::now().checked_add(self.0)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		validate_dimensions, BlockVerificationResult, BlockVerified, CellFetchStrategy, Confidence,
+		ConfigError, ExponentialConfig, FibonacciConfig, Mode, MultiaddrConfig, OptionBlockRange,
+		RetryConfig, RuntimeConfig, State, SyncStatus,
+	};
+	use crate::data::{mem_db::MemoryDB, Database, Key};
+	use avail_subxt::utils::H256;
+	use libp2p::{Multiaddr, PeerId};
+	use std::{collections::HashMap, env, fs, path::PathBuf, time::Duration};
+	use test_case::test_case;
+	use tokio::net::TcpListener;
+	use uuid::Uuid;
+
+	fn config_file(extension: &str, content: &str) -> PathBuf {
+		let path = env::temp_dir().join(format!("config-{}.{extension}", Uuid::new_v4()));
+		fs::write(&path, content).unwrap();
+		path
+	}
+
+	#[test]
+	fn test_sync_status() {
+		let db = MemoryDB::default();
+		let mut state = State {
+			latest: 12,
+			synced: Some(false),
+			..Default::default()
+		};
+		let status = state.sync_status(&db).unwrap();
+		assert_eq!(status.latest_verified_block, None);
+		assert_eq!(status.verified_blocks_in_window, 0);
+		assert!(status.verified_ranges.is_empty());
+		assert_eq!(
+			status.to_string(),
+			"no verified blocks, chain head 12, 0 blocks verified, syncing"
+		);
+
+		// Block 9 is not verified, but it is within the confidence achieved range
+		for block_number in [2, 3, 8, 10, 11] {
+			db.put(Key::VerifiedCellCount(block_number), 8u32).unwrap();
+		}
+		state.confidence_achieved.set(8);
+		state.confidence_achieved.set(11);
+		state.synced = Some(true);
+		let status = state.sync_status(&db).unwrap();
+		assert_eq!(
+			status,
+			SyncStatus {
+				latest_verified_block: Some(11),
+				chain_head: 12,
+				syncing: false,
+				verified_blocks_in_window: 3,
+				verified_ranges: vec![2..=3, 8..=8, 10..=11],
+			}
+		);
+		assert_eq!(
+			status.to_string(),
+			"latest verified block 11, chain head 12, 3 blocks verified"
+		);
+	}
+
+	#[test_case(None => false ; "not verified")]
+	#[test_case(Some(93.75) => false ; "below threshold")]
+	#[test_case(Some(99.9) => true ; "at threshold")]
+	#[test_case(Some(99.95) => true ; "above threshold")]
+	fn test_confidence_achieved(confidence: Option<f64>) -> bool {
+		let block = BlockVerified {
+			header_hash: H256::zero(),
+			block_num: 1,
+			extension: None,
+			confidence,
+			verification: None,
+		};
+		block.confidence_achieved(99.9)
+	}
+
+	#[test_case(99.9 => 99.9 ; "configured")]
+	#[test_case(99.999 => 99.99 ; "capped")]
+	fn test_effective_confidence(confidence: f64) -> f64 {
+		let cfg = RuntimeConfig {
+			confidence: Confidence::new(confidence).unwrap(),
+			..Default::default()
+		};
+		cfg.effective_confidence()
+	}
+
+	#[test_case("info" => Some("avail_light=info".to_string()) ; "simple level")]
+	#[test_case("DEBUG" => Some("avail_light=debug".to_string()) ; "uppercase level")]
+	#[test_case("libp2p=warn" => Some("libp2p=warn".to_string()) ; "directive")]
+	#[test_case("avail_light=loud" => None ; "invalid directive")]
+	#[test_case("inof" => None ; "misspelled level")]
+	#[test_case("" => None ; "empty level")]
+	fn test_log_filter_directives(log_level: &str) -> Option<String> {
+		let cfg = RuntimeConfig {
+			log_level: log_level.to_string(),
+			..Default::default()
+		};
+		cfg.log_filter_directives()
+			.ok()
+			.map(|filter| filter.to_string())
+	}
+
+	#[test]
+	fn test_log_filter_compound_directives() {
+		let cfg = RuntimeConfig {
+			log_level: "avail_light=debug,libp2p=warn".to_string(),
+			..Default::default()
+		};
+		let filter = cfg.log_filter_directives().unwrap().to_string();
+		assert!(filter.contains("avail_light=debug"));
+		assert!(filter.contains("libp2p=warn"));
+	}
+
+	#[test_case("dht_first" => Some(CellFetchStrategy::DHTFirst) ; "dht first")]
+	#[test_case("RPC_FIRST" => Some(CellFetchStrategy::RPCFirst) ; "rpc first")]
+	#[test_case("parallel" => Some(CellFetchStrategy::Parallel) ; "parallel")]
+	#[test_case("random" => None ; "invalid")]
+	fn test_cell_fetch_strategy(value: &str) -> Option<CellFetchStrategy> {
+		let strategy = CellFetchStrategy::try_from(value.to_string()).ok();
+		if let Some(strategy) = strategy {
+			assert_eq!(
+				CellFetchStrategy::try_from(String::from(strategy)).ok(),
+				Some(strategy)
+			);
+		}
+		strategy
+	}
+
+	#[test]
+	fn test_apply_overlay() {
+		let vars = HashMap::from([
+			("AVAIL_HTTP_SERVER_PORT", "8080"),
+			("AVAIL_CONFIDENCE", "92.5"),
+			("AVAIL_GENESIS_HASH", "1234"),
+			("AVAIL_APP_ID", "3"),
+			("AVAIL_FULL_NODE_WS", r#"["ws://a:9944","ws://b:9944"]"#),
+		]);
+		let mut cfg = RuntimeConfig::default();
+		cfg.apply_overlay(|name| vars.get(name).map(|value| value.to_string()))
+			.unwrap();
+
+		assert_eq!(cfg.http_server_port, 8080);
+		assert_eq!(*cfg.confidence, 92.5);
+		assert_eq!(cfg.genesis_hash, "1234");
+		assert_eq!(cfg.app_id, Some(3));
+		assert_eq!(cfg.full_node_ws, vec!["ws://a:9944", "ws://b:9944"]);
+		// Unset variables leave parameters unchanged
+		assert_eq!(cfg.log_level, RuntimeConfig::default().log_level);
+	}
+
+	#[test_case("AVAIL_CONFIDENCE", "100" ; "invalid confidence")]
+	#[test_case("AVAIL_HTTP_SERVER_PORT", "port" ; "invalid port")]
+	#[test_case("AVAIL_FULL_NODE_WS", "[]" ; "empty full node list")]
+	fn test_apply_invalid_overlay(name: &'static str, value: &'static str) {
+		let mut cfg = RuntimeConfig::default();
+		let result = cfg.apply_overlay(|var| (var == name).then(|| value.to_string()));
+		assert!(result.is_err());
+		assert_eq!(
+			cfg.http_server_port,
+			RuntimeConfig::default().http_server_port
+		);
+	}
+
+	#[test]
+	fn test_merge_runtime_config() {
+		let base = RuntimeConfig {
+			full_node_ws: vec!["ws://base:9944".to_string()],
+			log_level: "debug".to_string(),
+			app_id: Some(1),
+			http_server_port: 8000,
+			..Default::default()
+		};
+		let override_config = RuntimeConfig {
+			full_node_ws: vec!["ws://override:9944".to_string()],
+			log_level: "trace".to_string(),
+			max_cells_per_rpc: Some(10),
+			..Default::default()
+		};
+
+		let merged = base.merge(&override_config);
+		// Lists are replaced
+		assert_eq!(merged.full_node_ws, vec!["ws://override:9944".to_string()]);
+		// Non-default override values take precedence
+		assert_eq!(merged.log_level, "trace");
+		assert_eq!(merged.max_cells_per_rpc, Some(10));
+		// Default override values keep base values
+		assert_eq!(merged.app_id, Some(1));
+		assert_eq!(merged.http_server_port, 8000);
+		assert_eq!(merged.confidence, RuntimeConfig::default().confidence);
+	}
+
+	#[test]
+	fn test_merge_with_default_keeps_base() {
+		let base = RuntimeConfig {
+			full_node_ws: vec![],
+			log_format_json: true,
+			block_matrix_partition: Some(kate_recovery::matrix::Partition {
+				number: 1,
+				fraction: 10,
+			}),
+			..Default::default()
+		};
+
+		let merged = base.merge(&RuntimeConfig::default());
+		assert_eq!(
+			serde_json::to_value(&merged).unwrap(),
+			serde_json::to_value(&base).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_merge_nested_fields() {
+		let base = RuntimeConfig {
+			retry_config: RetryConfig::Fibonacci(FibonacciConfig {
+				base: 5,
+				max_delay: 10,
+				retries: 6,
+			}),
+			..Default::default()
+		};
+		let override_config = RuntimeConfig {
+			retry_config: RetryConfig::Fibonacci(FibonacciConfig {
+				base: 1,
+				max_delay: 20,
+				retries: 6,
+			}),
+			..Default::default()
+		};
+
+		let merged = base.merge(&override_config);
+		let RetryConfig::Fibonacci(retry_config) = merged.retry_config else {
+			panic!("Retry config type is kept");
+		};
+		// Default-valued nested field doesn't clobber base value
+		assert_eq!(retry_config.base, 5);
+		assert_eq!(retry_config.max_delay, 20);
+		assert_eq!(retry_config.retries, 6);
+	}
+
+	#[test_case(None, None => None ; "both unset")]
+	#[test_case(Some(10), None => Some(10) ; "unset override keeps base")]
+	#[test_case(None, Some(20) => Some(20) ; "override sets value")]
+	#[test_case(Some(10), Some(20) => Some(20) ; "override replaces base")]
+	fn test_merge_option_fields(base: Option<u32>, override_value: Option<u32>) -> Option<u32> {
+		let base = RuntimeConfig {
+			app_id: base,
+			..Default::default()
+		};
+		let override_config = RuntimeConfig {
+			app_id: override_value,
+			..Default::default()
+		};
+		base.merge(&override_config).app_id
+	}
+
+	#[test]
+	fn test_merge_default_values_dont_clobber_base() {
+		let base = RuntimeConfig {
+			http_server_port: 8000,
+			disable_rpc: true,
+			block_matrix_partition: Some(kate_recovery::matrix::Partition {
+				number: 1,
+				fraction: 10,
+			}),
+			..Default::default()
+		};
+		// Override explicitly sets default values
+		let override_config = RuntimeConfig {
+			http_server_port: RuntimeConfig::default().http_server_port,
+			disable_rpc: false,
+			block_matrix_partition: None,
+			..Default::default()
+		};
+
+		let merged = base.merge(&override_config);
+		assert_eq!(merged.http_server_port, 8000);
+		assert!(merged.disable_rpc);
+		assert!(merged.block_matrix_partition.is_some());
+	}
+
+	#[test_case(None => (true, false, None) ; "no app id")]
+	#[test_case(Some(0) => (false, true, Some(0)) ; "zero app id")]
+	#[test_case(Some(1) => (false, true, Some(1)) ; "non zero app id")]
+	fn test_mode_from_app_id(app_id: Option<u32>) -> (bool, bool, Option<u32>) {
+		let mode = Mode::from(app_id);
+		(mode.is_light_client(), mode.is_app_client(), mode.app_id())
+	}
+
+	#[test_case(RetryConfig::Exponential(ExponentialConfig { base: 2, max_delay: u64::MAX, retries: 100 }) ; "exponential")]
+	#[test_case(RetryConfig::Fibonacci(FibonacciConfig { base: 2, max_delay: u64::MAX, retries: 100 }) ; "fibonacci")]
+	fn test_retry_config_bounded(retry_config: RetryConfig) {
+		let max_backoff = Duration::from_secs(60);
+		let delays = retry_config.bounded(max_backoff);
+		assert_eq!(delays.len(), 100);
+		assert!(delays.iter().all(|&delay| delay <= max_backoff));
+	}
+
+	#[test_case(1, 4 => true ; "minimal matrix")]
+	#[test_case(1024, 256 => true ; "maximal matrix")]
+	#[test_case(0, 4 => false ; "zero rows")]
+	#[test_case(3, 4 => false ; "rows not power of two")]
+	#[test_case(2048, 4 => false ; "too many rows")]
+	#[test_case(1, 6 => false ; "columns not power of two")]
+	#[test_case(1, 512 => false ; "too many columns")]
+	fn test_validate_dimensions(rows: u16, cols: u16) -> bool {
+		validate_dimensions(rows, cols).is_ok()
+	}
+
+	#[test_case(50.0 => false ; "lower bound")]
+	#[test_case(50.1 => true ; "above lower bound")]
+	#[test_case(99.9 => true ; "below upper bound")]
+	#[test_case(100.0 => false ; "upper bound")]
+	#[test_case(f64::NAN => false ; "not a number")]
+	fn test_confidence_new(value: f64) -> bool {
+		Confidence::new(value).is_ok()
+	}
+
+	#[test]
+	fn test_confidence_serde() {
+		let confidence: Confidence = serde_json::from_str("99.9").unwrap();
+		assert_eq!(*confidence, 99.9);
+		assert_eq!(serde_json::to_string(&confidence).unwrap(), "99.9");
+		assert!(serde_json::from_str::<Confidence>("100.5").is_err());
+	}
+
+	#[test]
+	fn test_mode_display() {
+		assert_eq!(Mode::LightClient.to_string(), "light client");
+		assert_eq!(Mode::AppClient(1).to_string(), "app client (app_id: 1)");
+	}
+
+	#[test]
+	fn test_block_verification_result() {
+		let result = BlockVerificationResult {
+			block_number: 42,
+			cells_sampled: 10,
+			cells_verified: 10,
+			confidence: 99.90234375,
+			mode: Mode::AppClient(1),
+			duration: Duration::from_millis(1500),
+		};
+		assert_eq!(
+			result.to_string(),
+			"Block 42 verified by app client (app_id: 1): 10/10 cells, confidence 99.90234375%, duration 1.5s"
+		);
+
+		let json = serde_json::to_string(&result).unwrap();
+		let deserialized: BlockVerificationResult = serde_json::from_str(&json).unwrap();
+		assert_eq!(deserialized.block_number, 42);
+		assert_eq!(deserialized.mode.app_id(), Some(1));
+		assert_eq!(deserialized.duration, result.duration);
+	}
+
+	#[test_case("toml", "confidence = 95.0\nfull_node_ws = [\"ws://node:9944\"]\n" ; "toml")]
+	#[test_case("json", r#"{"confidence": 95.0, "full_node_ws": ["ws://node:9944"]}"# ; "json")]
+	fn test_runtime_config_from_file(extension: &str, content: &str) {
+		let path = config_file(extension, content);
+		let cfg = RuntimeConfig::from_file(&path).unwrap();
+		fs::remove_file(path).unwrap();
+
+		assert_eq!(*cfg.confidence, 95.0);
+		assert_eq!(cfg.full_node_ws, vec!["ws://node:9944".to_string()]);
+		// Missing parameters are set to defaults
+		assert_eq!(cfg.port, RuntimeConfig::default().port);
+	}
+
+	#[test_case("json", r#"{"full_node_ws": []}"#, "full_node_ws" ; "empty full node endpoints")]
+	#[test_case("toml", "rpc_failure_threshold = 0\n", "rpc_failure_threshold" ; "zero rpc failure threshold")]
+	#[test_case("toml", "peer_score_decay = 1.0\n", "peer_score_decay" ; "peer score decay out of range")]
+	#[test_case("toml", "peer_ban_threshold = -0.1\n", "peer_ban_threshold" ; "negative peer ban threshold")]
+	fn test_runtime_config_from_file_invalid(extension: &str, content: &str, field: &str) {
+		let path = config_file(extension, content);
+		let error = RuntimeConfig::from_file(&path).unwrap_err();
+		fs::remove_file(&path).unwrap();
+
+		assert!(error.to_string().contains(&path.display().to_string()));
+		assert!(matches!(
+			error.downcast_ref::<ConfigError>(),
+			Some(ConfigError::InvalidField { field: invalid_field, .. }) if *invalid_field == field
+		));
+	}
+
+	#[test_case("toml", "confidence = 120.0\n" ; "invalid toml value")]
+	#[test_case("json", r#"{"confidence": "# ; "invalid json")]
+	fn test_runtime_config_from_file_parse_error(extension: &str, content: &str) {
+		let path = config_file(extension, content);
+		let error = RuntimeConfig::from_file(&path).unwrap_err();
+		fs::remove_file(&path).unwrap();
+
+		let expected_path = path.display().to_string();
+		assert!(matches!(
+			error.downcast_ref::<ConfigError>(),
+			Some(ConfigError::Parse { path, .. }) if *path == expected_path
+		));
+	}
+
+	#[test]
+	fn test_runtime_config_from_missing_file() {
+		let path = env::temp_dir().join(format!("config-{}.toml", Uuid::new_v4()));
+		let error = RuntimeConfig::from_file(&path).unwrap_err();
+		assert!(matches!(
+			error.downcast_ref::<ConfigError>(),
+			Some(ConfigError::Read { .. })
+		));
+	}
+
+	#[tokio::test]
+	async fn test_validate_bootstraps() {
+		// Listener accepts connections and closes them immediately
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let open_port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			while let Ok((stream, _)) = listener.accept().await {
+				drop(stream);
+			}
+		});
+		let bootstraps = [
+			format!("/ip4/127.0.0.1/tcp/{open_port}"),
+			// Connections to port 0 are always refused
+			"/ip4/127.0.0.1/tcp/0".to_string(),
+			"/ip4/127.0.0.1/udp/39000/quic-v1".to_string(),
+		]
+		.map(|addr| (PeerId::random(), addr.parse::<Multiaddr>().unwrap()));
+		let cfg = RuntimeConfig {
+			bootstraps: bootstraps
+				.iter()
+				.cloned()
+				.map(MultiaddrConfig::PeerIdAndMultiaddr)
+				.collect(),
+			..Default::default()
+		};
+
+		let results = cfg.validate_bootstraps(Duration::from_secs(5)).await;
+		let reachable = results
+			.iter()
+			.map(|result| result.reachable)
+			.collect::<Vec<_>>();
+		assert_eq!(reachable, vec![true, false, false]);
+		assert_eq!(results[0].peer_id, bootstraps[0].0);
+	}
+}
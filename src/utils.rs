@@ -7,12 +7,13 @@ use avail_subxt::{
 		da_control::pallet::Call,
 		da_runtime::RuntimeCall,
 	},
+	config::substrate::{Digest, DigestItem},
 	primitives::{
 		grandpa::AuthorityId, grandpa::ConsensusLog, AppUncheckedExtrinsic, Header as DaHeader,
 	},
-	utils::H256,
+	utils::{AccountId32, MultiAddress, MultiSignature, H256},
 };
-use codec::Decode;
+use codec::{Compact, Decode, Encode};
 use color_eyre::{
 	eyre::{self, eyre, WrapErr},
 	Result,
@@ -21,6 +22,7 @@ use kate_recovery::{
 	data::Cell,
 	matrix::{Dimensions, Position},
 };
+use sp_core::blake2_256;
 
 pub fn decode_app_data(data: &[u8]) -> Result<Option<Vec<u8>>> {
 	let extrisic: AppUncheckedExtrinsic =
@@ -32,6 +34,37 @@ pub fn decode_app_data(data: &[u8]) -> Result<Option<Vec<u8>>> {
 	}
 }
 
+/// Decodes application ID of SCALE encoded extrinsic, without decoding the call.
+/// Returns `None` for unsigned extrinsics, or if extrinsic cannot be decoded.
+///
+/// Signed extrinsic is encoded as length prefix, version byte, signer address, signature,
+/// and signed extensions data: era, nonce, tip and application ID.
+pub fn decode_app_id(data: &[u8]) -> Option<u32> {
+	const SIGNED_FLAG: u8 = 0b1000_0000;
+	const IMMORTAL_ERA: u8 = 0;
+
+	let input = &mut &data[..];
+	Compact::<u32>::decode(input).ok()?;
+	if u8::decode(input).ok()? & SIGNED_FLAG == 0 {
+		return None;
+	}
+	MultiAddress::<AccountId32, u32>::decode(input).ok()?;
+	MultiSignature::decode(input).ok()?;
+	// Mortal era is encoded in two bytes
+	if u8::decode(input).ok()? != IMMORTAL_ERA {
+		u8::decode(input).ok()?;
+	}
+	Compact::<u32>::decode(input).ok()?;
+	Compact::<u128>::decode(input).ok()?;
+	Compact::<u32>::decode(input).ok().map(|app_id| app_id.0)
+}
+
+/// Checks if SCALE encoded extrinsic is submitted by an application, without decoding the call.
+/// Unsigned extrinsics and extrinsics with application ID 0 (system extrinsics) are not app extrinsics.
+pub fn is_app_extrinsic(data: &[u8]) -> bool {
+	decode_app_id(data).is_some_and(|app_id| app_id != 0)
+}
+
 /// Calculates confidence from given number of verified cells
 pub fn calculate_confidence(count: u32) -> f64 {
 	100f64 * (1f64 - 1f64 / 2u32.pow(count) as f64)
@@ -91,13 +124,12 @@ pub fn filter_auth_set_changes(header: &DaHeader) -> Vec<Vec<(AuthorityId, u64)>
 		.iter()
 		.filter_map(|e| match &e {
 			// UGHHH, why won't the b"FRNK" just work
-			avail_subxt::config::substrate::DigestItem::Consensus(
-				[b'F', b'R', b'N', b'K'],
-				data,
-			) => match ConsensusLog::<u32>::decode(&mut data.as_slice()) {
-				Ok(ConsensusLog::ScheduledChange(x)) => Some(x.next_authorities),
-				Ok(ConsensusLog::ForcedChange(_, x)) => Some(x.next_authorities),
-				_ => None,
+			DigestItem::Consensus([b'F', b'R', b'N', b'K'], data) => {
+				match ConsensusLog::<u32>::decode(&mut data.as_slice()) {
+					Ok(ConsensusLog::ScheduledChange(x)) => Some(x.next_authorities),
+					Ok(ConsensusLog::ForcedChange(_, x)) => Some(x.next_authorities),
+					_ => None,
+				}
 			},
 			_ => None,
 		})
@@ -105,6 +137,85 @@ pub fn filter_auth_set_changes(header: &DaHeader) -> Vec<Vec<(AuthorityId, u64)>
 	new_auths
 }
 
+/// Returns positions of the cells which contain data of given application, in the extended matrix.
+/// Cells are taken from the application range in the lookup, so cells of other applications
+/// sharing the same rows are not included.
+/// Returns empty list if application has no data in the block.
+pub fn app_positions(lookup: &DataLookup, app_id: u32, dimensions: &Dimensions) -> Vec<Position> {
+	let Some(range) = lookup.range_of(AppId(app_id)) else {
+		return vec![];
+	};
+	let cols = u32::from(dimensions.cols().get());
+	// Original rows are interleaved with the extension rows
+	let extension_factor = dimensions.extended_rows() / u32::from(dimensions.rows().get());
+	range
+		.map(|index| Position {
+			row: index / cols * extension_factor,
+			col: (index % cols) as u16,
+		})
+		.collect()
+}
+
+/// Application related queries on the block data lookup
+/// (emptiness check is provided by [`DataLookup::is_empty`])
+pub trait DataLookupExt {
+	/// Returns sorted list of applications which have data in the block
+	fn app_ids(&self) -> Vec<u32>;
+	/// Checks if application has data in the block
+	fn contains_app(&self, app_id: u32) -> bool;
+}
+
+impl DataLookupExt for DataLookup {
+	fn app_ids(&self) -> Vec<u32> {
+		let mut app_ids = CompactDataLookup::from_data_lookup(self)
+			.index
+			.into_iter()
+			.map(|item| item.app_id.0)
+			.collect::<Vec<_>>();
+		app_ids.sort_unstable();
+		app_ids.dedup();
+		app_ids
+	}
+
+	fn contains_app(&self, app_id: u32) -> bool {
+		self.range_of(AppId(app_id)).is_some()
+	}
+}
+
+/// Hashing of the block header
+pub trait HeaderExt {
+	/// Returns Blake2b-256 hash of the SCALE encoded header.
+	/// All header fields are hashed, so the digest must be the same as in the produced block.
+	fn hash(&self) -> H256;
+}
+
+impl HeaderExt for DaHeader {
+	fn hash(&self) -> H256 {
+		Encode::using_encoded(self, blake2_256).into()
+	}
+}
+
+/// Consensus related queries on the block header digest
+pub trait DigestExt {
+	/// Checks if digest contains any consensus log
+	fn has_consensus_log(&self) -> bool;
+	/// Returns engine ID of the first consensus log (e.g. `FRNK` for GRANDPA)
+	fn consensus_engine_id(&self) -> Option<[u8; 4]>;
+}
+
+impl DigestExt for Digest {
+	fn has_consensus_log(&self) -> bool {
+		self.consensus_engine_id().is_some()
+	}
+
+	fn consensus_engine_id(&self) -> Option<[u8; 4]> {
+		self.logs.iter().find_map(|log| match log {
+			DigestItem::Consensus(engine_id, _) => Some(*engine_id),
+			_ => None,
+		})
+	}
+}
+
 // TODO: Remove unused functions if not needed after next iteration
 
 #[allow(dead_code)]
@@ -129,11 +240,75 @@ fn diff_positions(positions: &[Position], cells: &[Cell]) -> Vec<Position> {
 
 #[cfg(test)]
 mod tests {
-	use super::{can_reconstruct, diff_positions};
+	use super::{
+		app_positions, can_reconstruct, decode_app_data, decode_app_id, diff_positions,
+		is_app_extrinsic, DataLookupExt, DigestExt, HeaderExt,
+	};
+	use avail_core::DataLookup;
+	use avail_subxt::{
+		api::runtime_types::avail_core::{
+			data_lookup::compact::CompactDataLookup,
+			header::extension::{v3, HeaderExtension},
+			kate_commitment::v3::KateCommitment,
+		},
+		config::substrate::{Digest, DigestItem},
+		primitives::{AppUncheckedExtrinsic, Header},
+		utils::H256,
+	};
+	use codec::{Compact, Decode, Encode};
+	use hex_literal::hex;
 	use kate_recovery::{
 		data::Cell,
 		matrix::{Dimensions, Position},
 	};
+	use proptest::{collection::btree_map, prop_assert, prop_assert_eq, proptest};
+	use test_case::test_case;
+
+	// Builds extrinsic with sr25519 signature and `remark` call
+	fn signed_extrinsic(era: &[u8], app_id: u32) -> Vec<u8> {
+		let mut extrinsic = vec![0x84, 0x00];
+		extrinsic.extend([1u8; 32]);
+		extrinsic.push(0x01);
+		extrinsic.extend([2u8; 64]);
+		extrinsic.extend(era);
+		extrinsic.extend(Compact(5u32).encode());
+		extrinsic.extend(Compact(0u128).encode());
+		extrinsic.extend(Compact(app_id).encode());
+		extrinsic.extend([0x00, 0x00, 0x04, 0xff]);
+		extrinsic.encode()
+	}
+
+	#[test_case(signed_extrinsic(&[0x00], 0) => Some(0) ; "immortal system extrinsic")]
+	#[test_case(signed_extrinsic(&[0x00], 7) => Some(7) ; "immortal app extrinsic")]
+	#[test_case(signed_extrinsic(&[0xe5, 0x03], 1024) => Some(1024) ; "mortal app extrinsic")]
+	#[test_case(vec![0x10, 0x04, 0x00, 0x00, 0x00] => None ; "unsigned extrinsic")]
+	#[test_case(signed_extrinsic(&[0x00], 7)[..40].to_vec() => None ; "truncated extrinsic")]
+	fn test_decode_app_id(extrinsic: Vec<u8>) -> Option<u32> {
+		decode_app_id(&extrinsic)
+	}
+
+	#[test]
+	fn test_decode_app_id_of_app_unchecked_extrinsic() {
+		// `submit_data` extrinsic with app ID 1 and `example` data, signed by Alice (see API v1 README)
+		let data = hex!("c5018400d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d01308e88ca257b65514b7b44fc1913a6a9af6abc34c3d22761b0e425674d68df7de26be1c8533a7bbd01fdb3a8daa5af77df6d3fb0a67cde8241f461f4fe16f188000000041d011c6578616d706c65");
+		let extrinsic = AppUncheckedExtrinsic::decode(&mut &data[..]).unwrap();
+		let encoded = extrinsic.encode();
+
+		assert_eq!(decode_app_id(&encoded), Some(1));
+		assert!(is_app_extrinsic(&encoded));
+		assert_eq!(
+			decode_app_data(&encoded).unwrap(),
+			Some(b"example".to_vec())
+		);
+	}
+
+	#[test_case(signed_extrinsic(&[0x00], 0) => false ; "system extrinsic")]
+	#[test_case(signed_extrinsic(&[0xe5, 0x03], 7) => true ; "app extrinsic")]
+	#[test_case(vec![0x10, 0x04, 0x00, 0x00, 0x00] => false ; "unsigned extrinsic")]
+	#[test_case(vec![] => false ; "empty data")]
+	fn test_is_app_extrinsic(extrinsic: Vec<u8>) -> bool {
+		is_app_extrinsic(&extrinsic)
+	}
 
 	fn position(row: u32, col: u16) -> Position {
 		Position { row, col }
@@ -193,4 +368,128 @@ mod tests {
 		assert_eq!(diff_positions(&positions, &cells)[0], position(0, 0));
 		assert_eq!(diff_positions(&positions, &cells)[1], position(1, 1));
 	}
+
+	#[test]
+	fn test_app_positions() {
+		let position = |row, col| Position { row, col };
+		let dimensions = Dimensions::new(1, 4).unwrap();
+		let id_lens: Vec<(u32, usize)> = vec![(0, 1), (1, 2)];
+		let lookup = DataLookup::from_id_and_len_iter(id_lens.into_iter()).unwrap();
+
+		assert!(app_positions(&lookup, 2, &dimensions).is_empty());
+		assert_eq!(app_positions(&lookup, 0, &dimensions), vec![position(0, 0)]);
+		assert_eq!(
+			app_positions(&lookup, 1, &dimensions),
+			vec![position(0, 1), position(0, 2)]
+		);
+	}
+
+	#[test]
+	fn test_app_positions_multiple_rows() {
+		let position = |row, col| Position { row, col };
+		let dimensions = Dimensions::new(2, 4).unwrap();
+		let id_lens: Vec<(u32, usize)> = vec![(0, 3), (1, 3)];
+		let lookup = DataLookup::from_id_and_len_iter(id_lens.into_iter()).unwrap();
+
+		// Second original row is the third row of the extended matrix
+		assert_eq!(
+			app_positions(&lookup, 1, &dimensions),
+			vec![position(0, 3), position(2, 0), position(2, 1)]
+		);
+	}
+
+	#[test]
+	fn test_data_lookup_ext() {
+		let lookup =
+			DataLookup::from_id_and_len_iter([(0u32, 1usize), (3, 2)].into_iter()).unwrap();
+		assert_eq!(lookup.app_ids(), vec![0, 3]);
+		assert!(lookup.contains_app(3));
+		assert!(!lookup.contains_app(1));
+	}
+
+	proptest! {
+	#[test]
+	fn app_ids_sorted_without_duplicates(lens in btree_map(0u32..1000, 1usize..10, 0..20)) {
+		let lookup = DataLookup::from_id_and_len_iter(lens.clone().into_iter()).unwrap();
+		let app_ids = lookup.app_ids();
+		prop_assert!(app_ids.windows(2).all(|ids| ids[0] < ids[1]));
+		prop_assert_eq!(app_ids, lens.into_keys().collect::<Vec<_>>());
+	}
+	}
+
+	fn digest(logs: Vec<DigestItem>) -> Digest {
+		Digest { logs }
+	}
+
+	#[test]
+	fn test_digest_without_consensus_log() {
+		assert!(!digest(vec![]).has_consensus_log());
+		let digest = digest(vec![
+			DigestItem::PreRuntime(*b"BABE", vec![2, 0, 0, 0, 0]),
+			DigestItem::Seal(*b"BABE", vec![0; 64]),
+		]);
+		assert!(!digest.has_consensus_log());
+		assert_eq!(digest.consensus_engine_id(), None);
+	}
+
+	#[test]
+	fn test_digest_with_consensus_log() {
+		let digest = digest(vec![
+			DigestItem::PreRuntime(*b"BABE", vec![2, 0, 0, 0, 0]),
+			DigestItem::Consensus(*b"FRNK", vec![1, 0]),
+			DigestItem::Consensus(*b"BABE", vec![1, 0]),
+			DigestItem::Seal(*b"BABE", vec![0; 64]),
+		]);
+		assert!(digest.has_consensus_log());
+		assert_eq!(digest.consensus_engine_id(), Some(*b"FRNK"));
+	}
+
+	/// Header of the block 2 used in the sync client tests, with the given digest logs
+	fn block_2_header(logs: Vec<DigestItem>) -> Header {
+		Header {
+			parent_hash: hex!("2a75ea712b4b2c360cb7c0cdd806de4e9363ff7e37ce30788d487a258604dba3")
+				.into(),
+			number: 2,
+			state_root: hex!("6f41d5a26a34f7bc3a09d4811b444c09daaebbd5c5d67c4525f42b3ed11bef86")
+				.into(),
+			extrinsics_root: hex!(
+				"3027e34c2c75756c22770e6a3650ad68f3c9e44eed3c5ab4471742fe96678dae"
+			)
+			.into(),
+			digest: Digest { logs },
+			extension: HeaderExtension::V3(v3::HeaderExtension {
+				commitment: KateCommitment {
+					rows: 1,
+					cols: 4,
+					data_root: H256::zero(),
+					commitment: [
+						hex!("b50a68fb21ab57c00dc35d7fd74e72c05f5ca70a311114ccde6646daad121e31e80a89bbbad8618c10213438aad076f2"),
+						hex!("b50a68fb21ab57c00dc35d7fd74e72c05f5ca70a311114ccde6646daad121e31e80a89bbbad8618c10213438aad076f2"),
+					]
+					.concat(),
+				},
+				app_lookup: CompactDataLookup {
+					size: 1,
+					index: vec![],
+				},
+			}),
+		}
+	}
+
+	#[test]
+	fn test_header_hash() {
+		// Expected hashes are Blake2b-256 of the headers encoded independently of the SCALE codec
+		let header = block_2_header(vec![
+			// BABE secondary plain pre-digest, authority 0, slot 284140278
+			DigestItem::PreRuntime(*b"BABE", hex!("0200000000f6a2ef1000000000").to_vec()),
+			DigestItem::Seal(*b"BABE", (0..64).collect()),
+		]);
+		let expected = hex!("aada5b762cd451303843305122305a642bbc550af756d035a8bd9d003612fa12");
+		assert_eq!(header.hash(), H256::from(expected));
+
+		// Digest is part of the hash
+		let header = block_2_header(vec![]);
+		let expected = hex!("b4249784cbf86045bfffa5bfe5483b8660ba0433dcac173155290fb1664c8d1f");
+		assert_eq!(header.hash(), H256::from(expected));
+	}
 }